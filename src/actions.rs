@@ -0,0 +1,400 @@
+use bevy::prelude::*;
+use bevy::input::InputSystem;
+use bevy::utils::HashMap;
+use bevy::utils::HashSet;
+use crate::settings::config_dir;
+use crate::gamestate::GameState;
+use crate::replay::replay_input_system;
+use crate::simulation::SimulationSet;
+
+pub struct ActionsPlugin;
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bindings>()
+            .init_resource::<ActionState>()
+            .add_systems(PreUpdate, (keyboard_action_system, gamepad_action_system).chain().in_set(ActionSystems).after(InputSystem))
+            .add_systems(OnEnter(GameState::Loading), reset_tick_input)
+            .add_systems(FixedUpdate, action_tick_system.in_set(SimulationSet::Input).after(replay_input_system).run_if(in_state(GameState::Game)));
+    }
+}
+
+/// Systems filling in [`ActionState`], anything reading it in `PreUpdate` must run after these
+#[derive(SystemSet, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct ActionSystems;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum Action {
+    RotateLeft,
+    RotateRight,
+    FireRegular,
+    FireGuided,
+    FireCounter,
+    CycleTrack,
+    DesignateTrack,
+    SonarMode,
+    DeployDecoy,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::RotateLeft,
+        Action::RotateRight,
+        Action::FireRegular,
+        Action::FireGuided,
+        Action::FireCounter,
+        Action::CycleTrack,
+        Action::DesignateTrack,
+        Action::SonarMode,
+        Action::DeployDecoy,
+        Action::Pause,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::RotateLeft => "Rotate left",
+            Action::RotateRight => "Rotate right",
+            Action::FireRegular => "Fire regular",
+            Action::FireGuided => "Fire guided",
+            Action::FireCounter => "Fire counter",
+            Action::CycleTrack => "Next contact",
+            Action::DesignateTrack => "Designate target",
+            Action::SonarMode => "Sonar mode",
+            Action::DeployDecoy => "Drop decoy",
+            Action::Pause => "Pause",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Action::RotateLeft => "rotate_left",
+            Action::RotateRight => "rotate_right",
+            Action::FireRegular => "fire_regular",
+            Action::FireGuided => "fire_guided",
+            Action::FireCounter => "fire_counter",
+            Action::CycleTrack => "cycle_track",
+            Action::DesignateTrack => "designate_track",
+            Action::SonarMode => "sonar_mode",
+            Action::DeployDecoy => "deploy_decoy",
+            Action::Pause => "pause",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// Directional navigation and confirmation for menus, independent of the gameplay bindings
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Next,
+    Previous,
+    Confirm,
+}
+
+/// Fixed gamepad layout: triggers and face buttons fire, start pauses
+const GAMEPAD_ACTIONS: [(GamepadButtonType, Action); 10] = [
+    (GamepadButtonType::RightTrigger2, Action::FireRegular),
+    (GamepadButtonType::South, Action::FireRegular),
+    (GamepadButtonType::LeftTrigger2, Action::FireGuided),
+    (GamepadButtonType::East, Action::FireGuided),
+    (GamepadButtonType::West, Action::FireCounter),
+    (GamepadButtonType::RightTrigger, Action::CycleTrack),
+    (GamepadButtonType::North, Action::DesignateTrack),
+    (GamepadButtonType::LeftTrigger, Action::SonarMode),
+    (GamepadButtonType::LeftThumb, Action::DeployDecoy),
+    (GamepadButtonType::Start, Action::Pause),
+];
+
+const GAMEPAD_MENU_ACTIONS: [(GamepadButtonType, MenuAction); 5] = [
+    (GamepadButtonType::DPadUp, MenuAction::Up),
+    (GamepadButtonType::DPadDown, MenuAction::Down),
+    (GamepadButtonType::DPadLeft, MenuAction::Left),
+    (GamepadButtonType::DPadRight, MenuAction::Right),
+    (GamepadButtonType::South, MenuAction::Confirm),
+];
+
+const KEYBOARD_MENU_ACTIONS: [(KeyCode, MenuAction); 6] = [
+    (KeyCode::ArrowUp, MenuAction::Up),
+    (KeyCode::ArrowDown, MenuAction::Down),
+    (KeyCode::ArrowLeft, MenuAction::Left),
+    (KeyCode::ArrowRight, MenuAction::Right),
+    (KeyCode::Enter, MenuAction::Confirm),
+    (KeyCode::NumpadEnter, MenuAction::Confirm),
+];
+
+const STICK_DEADZONE: f32 = 0.15;
+
+/// Keys the player is allowed to bind an action to
+const BINDABLE_KEYS: [KeyCode; 52] = [
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+    KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+    KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+    KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+    KeyCode::KeyY, KeyCode::KeyZ,
+    KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    KeyCode::ArrowLeft, KeyCode::ArrowRight, KeyCode::ArrowUp, KeyCode::ArrowDown,
+    KeyCode::Space, KeyCode::Enter, KeyCode::Tab, KeyCode::Escape, KeyCode::Backspace,
+    KeyCode::ShiftLeft, KeyCode::ShiftRight, KeyCode::ControlLeft, KeyCode::ControlRight,
+    KeyCode::AltLeft, KeyCode::AltRight, KeyCode::Backquote,
+];
+
+pub fn is_bindable(key: KeyCode) -> bool {
+    BINDABLE_KEYS.contains(&key)
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.into_iter().find(|key| format!("{:?}", key) == name)
+}
+
+/// Short, human readable name of a key for the HUD and settings screen
+pub fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::ArrowLeft => "<-".to_string(),
+        KeyCode::ArrowRight => "->".to_string(),
+        KeyCode::ArrowUp => "UP".to_string(),
+        KeyCode::ArrowDown => "DOWN".to_string(),
+        KeyCode::ShiftLeft | KeyCode::ShiftRight => "SHIFT".to_string(),
+        KeyCode::ControlLeft | KeyCode::ControlRight => "CTRL".to_string(),
+        KeyCode::AltLeft | KeyCode::AltRight => "ALT".to_string(),
+        KeyCode::Escape => "ESC".to_string(),
+        KeyCode::Backquote => "`".to_string(),
+        _ => {
+            let name = format!("{:?}", key);
+            name.strip_prefix("Key")
+                .or_else(|| name.strip_prefix("Digit"))
+                .unwrap_or(&name)
+                .to_uppercase()
+        }
+    }
+}
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct Bindings {
+    keys: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        let mut keys = HashMap::new();
+        keys.insert(Action::RotateLeft, vec![KeyCode::KeyA, KeyCode::ArrowLeft]);
+        keys.insert(Action::RotateRight, vec![KeyCode::KeyD, KeyCode::ArrowRight]);
+        keys.insert(Action::FireRegular, vec![KeyCode::Space]);
+        keys.insert(Action::FireGuided, vec![KeyCode::ShiftLeft]);
+        keys.insert(Action::FireCounter, vec![KeyCode::ControlLeft]);
+        keys.insert(Action::CycleTrack, vec![KeyCode::KeyQ]);
+        keys.insert(Action::DesignateTrack, vec![KeyCode::KeyE]);
+        keys.insert(Action::SonarMode, vec![KeyCode::KeyR]);
+        keys.insert(Action::DeployDecoy, vec![KeyCode::KeyF]);
+        keys.insert(Action::Pause, vec![KeyCode::Escape]);
+        Bindings { keys }
+    }
+}
+
+impl Bindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    /// Binds `key` as the only key for `action`, taking it away from any other action. Refused, returning
+    /// false, when it is the last key left to pause with, or there would be no way back out of a game.
+    pub fn rebind(&mut self, action: Action, key: KeyCode) -> bool {
+        if action != Action::Pause && self.keys(Action::Pause) == [key] {
+            return false;
+        }
+        for keys in self.keys.values_mut() {
+            keys.retain(|k| *k != key);
+        }
+        self.keys.insert(action, vec![key]);
+        true
+    }
+
+    /// e.g. "A/<-"
+    pub fn describe(&self, action: Action) -> String {
+        let keys = self.keys(action);
+        if keys.is_empty() {
+            return "-".to_string();
+        }
+        keys.iter().map(|key| key_label(*key)).collect::<Vec<String>>().join("/")
+    }
+
+    pub fn load() -> Bindings {
+        match std::fs::read_to_string(config_dir().join("bindings.cfg")) {
+            Ok(contents) => Bindings::parse(&contents),
+            Err(_) => Bindings::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let dir = config_dir();
+        let _ = std::fs::create_dir_all(&dir);
+        let path = dir.join("bindings.cfg");
+        if let Err(e) = std::fs::write(&path, self.serialize()) {
+            warn!("Could not save bindings to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn parse(contents: &str) -> Bindings {
+        let mut bindings = Bindings::default();
+        let mut listed = HashSet::new();
+        for line in contents.lines() {
+            let Some((name, value)) = line.split_once('=') else { continue; };
+            let Some(action) = Action::from_name(name.trim()) else { continue; };
+            let keys: Vec<KeyCode> = value.split(',').filter_map(|k| key_from_name(k.trim())).collect();
+            bindings.keys.insert(action, keys);
+            listed.insert(action);
+        }
+        // Files saved before an action existed don't list it, and its default key may since have been
+        // taken by the player for something else
+        let taken: HashSet<KeyCode> = listed.iter().flat_map(|action| bindings.keys(*action).to_vec()).collect();
+        for (action, keys) in bindings.keys.iter_mut() {
+            if !listed.contains(action) {
+                keys.retain(|key| !taken.contains(key));
+            }
+        }
+        bindings
+    }
+
+    pub fn serialize(&self) -> String {
+        Action::ALL.iter().map(|action| {
+            let keys: Vec<String> = self.keys(*action).iter().map(|key| format!("{:?}", key)).collect();
+            format!("{} = {}\n", action.name(), keys.join(", "))
+        }).collect()
+    }
+}
+
+/// Actions held down / pressed this frame, read by gameplay systems instead of raw input
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    menu_just_pressed: HashSet<MenuAction>,
+    rotation: f32,
+    /// What was pressed as of the last tick, see `tick_just_pressed`
+    tick_pressed: HashSet<Action>,
+    tick_just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    /// How hard the player is steering, from -1.0 (full right) to 1.0 (full left)
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn menu_just_pressed(&self, action: MenuAction) -> bool {
+        self.menu_just_pressed.contains(&action)
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Pressed this tick but not the one before. Frames and ticks don't line up, so gameplay on the
+    /// fixed timestep uses this instead of `just_pressed`, and replays press on the same ticks.
+    pub fn tick_just_pressed(&self, action: Action) -> bool {
+        self.tick_just_pressed.contains(&action)
+    }
+
+    /// Overrides what the player is holding, used to feed recorded input back in
+    pub fn set_pressed(&mut self, action: Action, pressed: bool) {
+        if pressed {
+            self.pressed.insert(action);
+        } else {
+            self.pressed.remove(&action);
+        }
+    }
+
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+    }
+}
+
+fn keyboard_action_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<Bindings>,
+    mut action_state: ResMut<ActionState>,
+) {
+    action_state.pressed.clear();
+    action_state.just_pressed.clear();
+    action_state.menu_just_pressed.clear();
+    for action in Action::ALL {
+        let keys = bindings.keys(action);
+        if keyboard_input.any_pressed(keys.iter().copied()) {
+            action_state.pressed.insert(action);
+        }
+        if keyboard_input.any_just_pressed(keys.iter().copied()) {
+            action_state.just_pressed.insert(action);
+        }
+    }
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for (key, action) in KEYBOARD_MENU_ACTIONS {
+        if keyboard_input.just_pressed(key) {
+            action_state.menu_just_pressed.insert(action);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        action_state.menu_just_pressed.insert(if shift { MenuAction::Previous } else { MenuAction::Next });
+    }
+    action_state.rotation = if action_state.pressed(Action::RotateLeft) {
+        1.0
+    } else if action_state.pressed(Action::RotateRight) {
+        -1.0
+    } else {
+        0.0
+    };
+}
+
+/// Settles this tick's presses once its input is final, including input played back from a replay
+fn action_tick_system(
+    mut action_state: ResMut<ActionState>,
+) {
+    let action_state = &mut *action_state;
+    action_state.tick_just_pressed = action_state.pressed.difference(&action_state.tick_pressed).copied().collect();
+    action_state.tick_pressed = action_state.pressed.clone();
+}
+
+// Every game starts with nothing held, whatever was pressed in the menus
+fn reset_tick_input(
+    mut action_state: ResMut<ActionState>,
+) {
+    action_state.tick_pressed.clear();
+    action_state.tick_just_pressed.clear();
+}
+
+fn gamepad_action_system(
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut action_state: ResMut<ActionState>,
+) {
+    for gamepad in gamepads.iter() {
+        for (button_type, action) in GAMEPAD_ACTIONS {
+            let button = GamepadButton::new(gamepad, button_type);
+            if buttons.pressed(button) {
+                action_state.pressed.insert(action);
+            }
+            if buttons.just_pressed(button) {
+                action_state.just_pressed.insert(action);
+            }
+        }
+        for (button_type, action) in GAMEPAD_MENU_ACTIONS {
+            if buttons.just_pressed(GamepadButton::new(gamepad, button_type)) {
+                action_state.menu_just_pressed.insert(action);
+            }
+        }
+        let stick = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.0);
+        if stick.abs() > STICK_DEADZONE && stick.abs() > action_state.rotation.abs() {
+            action_state.rotation = -stick.clamp(-1.0, 1.0);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use crate::gamestate::GameState;
+use crate::gamestate::despawn_system;
+use crate::gamestate::GameDespawnable;
+use crate::gamestate::PlayArea;
+use crate::sonar::Pingable;
+use crate::sonar::Signature;
+use crate::enemy::spawn_enemy;
+use crate::simulation::GameRng;
+use crate::simulation::SimulationSet;
+use crate::simulation::heading;
+use rand::Rng;
+
+/// Whales and schools of fish: harmless, but they echo like anything else
+pub struct BiologicsPlugin;
+impl Plugin for BiologicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Loading), spawn_biologics.after(spawn_enemy))
+            .add_systems(FixedUpdate, biologic_movement_system.in_set(SimulationSet::Movement).run_if(in_state(GameState::Game)))
+            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>);
+    }
+}
+
+const WHALES: usize = 1;
+const SCHOOLS: usize = 2;
+/// Chance per second that a biologic picks a new heading
+const WANDER_CHANCE: f64 = 0.1;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum BiologicKind {
+    Whale,
+    School,
+}
+
+impl BiologicKind {
+    fn speed(self) -> f32 {
+        match self {
+            BiologicKind::Whale => 6.0,
+            BiologicKind::School => 10.0,
+        }
+    }
+
+    fn signature(self) -> Signature {
+        match self {
+            BiologicKind::Whale => Signature::WHALE,
+            BiologicKind::School => Signature::SCHOOL,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Biologic {
+    pub kind: BiologicKind,
+    pub velocity: Vec2,
+    /// Keeps to within this distance of the center
+    roam: f32,
+}
+
+fn random_velocity(rng: &mut GameRng, kind: BiologicKind) -> Vec2 {
+    Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU)) * kind.speed()
+}
+
+// Runs after the enemies are placed so both draw from the seeded rng in the same order every game
+fn spawn_biologics(
+    mut commands: Commands,
+    play_area: Res<PlayArea>,
+    mut rng: ResMut<GameRng>,
+) {
+    let roam = play_area.height / 2.0;
+    let kinds = std::iter::repeat_n(BiologicKind::Whale, WHALES)
+        .chain(std::iter::repeat_n(BiologicKind::School, SCHOOLS));
+    for kind in kinds {
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let distance = rng.gen_range(roam * 0.3..roam * 0.9);
+        let position = Vec2::from_angle(angle) * distance;
+        let velocity = random_velocity(&mut rng, kind);
+        commands.spawn((
+            // Never drawn, the scope only shows its echoes like any other contact's
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::NONE,
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(0.0))
+                    .with_rotation(Quat::from_rotation_z(heading(velocity))),
+                ..default()
+            },
+            Biologic {
+                kind,
+                velocity,
+                roam,
+            },
+            Pingable::default(),
+            kind.signature(),
+            GameDespawnable,
+        ));
+    }
+}
+
+fn biologic_movement_system(
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    mut query: Query<(&mut Biologic, &mut Transform)>,
+) {
+    let wander = (WANDER_CHANCE * time.delta_seconds_f64()).min(1.0);
+    for (mut biologic, mut transform) in query.iter_mut() {
+        let position = transform.translation.xy();
+        if position.length() > biologic.roam {
+            // Turn back in towards the scope
+            biologic.velocity = -position.normalize() * biologic.kind.speed();
+        } else if rng.gen_bool(wander) {
+            biologic.velocity = random_velocity(&mut rng, biologic.kind);
+        }
+        transform.translation += (biologic.velocity * time.delta_seconds()).extend(0.0);
+        transform.rotation = Quat::from_rotation_z(heading(biologic.velocity));
+    }
+}
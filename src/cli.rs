@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+use bevy::prelude::*;
+use bevy::app::AppExit;
+use crate::gamestate::GameState;
+use crate::diagnostics::DiagnosticsCsv;
+use crate::enemy::Enemy;
+use crate::enemy::Wave;
+use crate::player::Player;
+use crate::replay::Playback;
+use crate::replay::Replay;
+use crate::replay::StartReplay;
+use crate::settings::Difficulty;
+use crate::settings::Settings;
+use crate::simulation::GameRng;
+use crate::simulation::SimulationTick;
+
+pub const USAGE: &str = "\
+Usage: miles-below-darkness [OPTIONS]
+
+Options:
+  --seed <N>            Play every game with this seed
+  --wave <N>            Start games at wave N
+  --difficulty <NAME>   easy, normal or hard
+  --size <WxH>          Window size, e.g. 1280x720
+  --windowed            Run in a window
+  --fullscreen          Run fullscreen
+  --skip-menu           Start a game right away
+  --replay <FILE>       Watch a replay file
+  --headless            Run without window or sound as fast as possible, then print a summary;
+                        starts right away and stops at game over, the end of the replay or --ticks
+  --ticks <N>           With --headless, stop after N ticks
+  --diagnostics-csv <FILE>
+                        Write FPS, entity counts and system timings to FILE, one row per frame
+  -h, --help            Show this message
+
+Settings given here apply to this session only, unless the settings screen is opened:
+leaving it saves everything it shows.";
+
+/// Options given on the command line, everything not given keeps its usual value
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Cli {
+    pub seed: Option<u64>,
+    pub wave: Option<u32>,
+    pub difficulty: Option<Difficulty>,
+    pub size: Option<(f32, f32)>,
+    pub fullscreen: Option<bool>,
+    pub skip_menu: bool,
+    pub headless: bool,
+    pub replay: Option<PathBuf>,
+    pub ticks: Option<u32>,
+    pub diagnostics_csv: Option<PathBuf>,
+    pub help: bool,
+}
+
+impl Cli {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+            match arg.as_str() {
+                "--seed" => cli.seed = Some(parse_number(&value("--seed")?, "--seed")?),
+                "--wave" => {
+                    let wave = parse_number(&value("--wave")?, "--wave")?;
+                    if wave == 0 {
+                        return Err("--wave starts at 1".to_string());
+                    }
+                    cli.wave = Some(wave);
+                }
+                "--difficulty" => {
+                    let name = value("--difficulty")?;
+                    cli.difficulty = Some(Difficulty::from_name(&name).ok_or_else(|| format!("unknown difficulty '{}'", name))?);
+                }
+                "--size" => {
+                    let size = value("--size")?;
+                    let parsed = size.split_once('x').and_then(|(w, h)| Some((w.parse::<f32>().ok()?, h.parse::<f32>().ok()?)));
+                    match parsed {
+                        Some((w, h)) if w >= 1.0 && h >= 1.0 => cli.size = Some((w, h)),
+                        _ => return Err(format!("--size expects WIDTHxHEIGHT, got '{}'", size)),
+                    }
+                }
+                "--windowed" => cli.fullscreen = Some(false),
+                "--fullscreen" => cli.fullscreen = Some(true),
+                "--skip-menu" => cli.skip_menu = true,
+                "--headless" => cli.headless = true,
+                "--replay" => cli.replay = Some(PathBuf::from(value("--replay")?)),
+                "--ticks" => cli.ticks = Some(parse_number(&value("--ticks")?, "--ticks")?),
+                "--diagnostics-csv" => cli.diagnostics_csv = Some(PathBuf::from(value("--diagnostics-csv")?)),
+                "-h" | "--help" => cli.help = true,
+                other => return Err(format!("unknown option '{}'", other)),
+            }
+        }
+        if cli.ticks.is_some() && !cli.headless {
+            return Err("--ticks only works with --headless".to_string());
+        }
+        Ok(cli)
+    }
+
+    /// Overrides for the settings the window is created with
+    pub fn apply_to_settings(&self, settings: &mut Settings) {
+        if let Some(size) = self.size {
+            settings.resolution = size;
+        }
+        if let Some(fullscreen) = self.fullscreen {
+            settings.fullscreen = fullscreen;
+        }
+        if let Some(difficulty) = self.difficulty {
+            settings.difficulty = difficulty;
+        }
+    }
+
+    /// Applies everything else to an app built by `build_app` or `build_headless_app`
+    pub fn configure(&self, app: &mut App) -> Result<(), String> {
+        self.apply_to_settings(&mut app.world.resource_mut::<Settings>());
+        if let Some(seed) = self.seed {
+            app.insert_resource(GameRng::pinned(seed));
+        }
+        if let Some(wave) = self.wave {
+            app.world.resource_mut::<Wave>().start = wave;
+        }
+        if let Some(path) = &self.replay {
+            let replay = Replay::load(path).map_err(|e| format!("could not open replay {}: {}", path.display(), e))?;
+            app.world.send_event(StartReplay(replay));
+        } else if self.skip_menu || self.headless {
+            app.world.resource_mut::<NextState<GameState>>().set(GameState::Loading);
+        }
+        if let Some(path) = &self.diagnostics_csv {
+            let csv = DiagnosticsCsv::create(path).map_err(|e| format!("could not create {}: {}", path.display(), e))?;
+            app.insert_resource(csv);
+        }
+        if self.headless {
+            app.insert_resource(HeadlessLimit(self.ticks))
+                .add_systems(Update, headless_exit_system);
+        }
+        Ok(())
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} expects a number, got '{}'", name, value))
+}
+
+#[derive(Resource)]
+struct HeadlessLimit(Option<u32>);
+
+/// Ends a headless run once there is nothing left to simulate, printing where it got to
+#[allow(clippy::too_many_arguments)]
+fn headless_exit_system(
+    state: Res<State<GameState>>,
+    tick: Res<SimulationTick>,
+    limit: Res<HeadlessLimit>,
+    playback: Res<Playback>,
+    rng: Res<GameRng>,
+    wave: Res<Wave>,
+    player: Query<&Player>,
+    enemies: Query<(), With<Enemy>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    let reason = match state.get() {
+        GameState::GameOver => "game over",
+        GameState::Game if playback.active() && tick.0 >= playback.ticks() => "end of replay",
+        GameState::Game if limit.0.is_some_and(|ticks| tick.0 >= ticks) => "tick limit",
+        _ => return,
+    };
+    let hull = player.get_single().map_or(0, |player| player.hull());
+    println!(
+        "{}: seed {} wave {} tick {} hull {} enemies {}",
+        reason, rng.seed(), wave.current, tick.0, hull, enemies.iter().count(),
+    );
+    app_exit_events.send(AppExit);
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use bevy::prelude::*;
+use bevy::ecs::system::SystemId;
+use bevy::input::InputSystem;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::window::ReceivedCharacter;
+use crate::actions::ActionSystems;
+use crate::gamestate::GameState;
+use crate::player::Player;
+use crate::replay::Recording;
+
+pub struct ConsolePlugin;
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .init_resource::<ConsoleCommands>()
+            .add_systems(Startup, spawn_console)
+            .add_systems(PreUpdate, console_input_system.after(InputSystem).before(ActionSystems))
+            .add_systems(Update, (run_console_commands, console_view_system).chain())
+            .add_console_command("help", "", "List commands", help_command)
+            .add_console_command("state", "<name>", "Switch to menu, loading, game, pause, settings, gameover or replays", state_command)
+            .add_console_command("count", "", "Count entities, grouped by their game components", count_command);
+    }
+}
+
+const TOGGLE_KEY: KeyCode = KeyCode::F1;
+const LOG_LINES: usize = 14;
+
+/// What a command prints back: `Ok` for output, `Err` for a usage or input mistake
+pub type ConsoleResult = Result<String, String>;
+
+/// Open state, the line being typed and the scrollback
+#[derive(Resource, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    log: Vec<String>,
+    history: Vec<String>,
+    /// Position while browsing `history` with the arrow keys
+    history_index: Option<usize>,
+    pending: Vec<String>,
+}
+
+impl Console {
+    /// Queues a line to be run as if typed, e.g. from a test
+    pub fn submit(&mut self, line: &str) {
+        self.pending.push(line.to_string());
+    }
+
+    pub fn log(&self) -> &[String] {
+        &self.log
+    }
+}
+
+struct ConsoleCommand {
+    args: &'static str,
+    help: &'static str,
+    system: SystemId<Vec<String>, ConsoleResult>,
+}
+
+#[derive(Resource, Default)]
+struct ConsoleCommands(BTreeMap<&'static str, ConsoleCommand>);
+
+pub trait ConsoleCommandsExt {
+    /// Registers `system` as console command `name`; it gets the words typed after the name
+    fn add_console_command<M>(
+        &mut self,
+        name: &'static str,
+        args: &'static str,
+        help: &'static str,
+        system: impl IntoSystem<Vec<String>, ConsoleResult, M> + 'static,
+    ) -> &mut Self;
+}
+
+impl ConsoleCommandsExt for App {
+    fn add_console_command<M>(
+        &mut self,
+        name: &'static str,
+        args: &'static str,
+        help: &'static str,
+        system: impl IntoSystem<Vec<String>, ConsoleResult, M> + 'static,
+    ) -> &mut Self {
+        let system = self.world.register_system(system);
+        self.world.get_resource_or_insert_with(ConsoleCommands::default).0.insert(name, ConsoleCommand { args, help, system });
+        self
+    }
+}
+
+/// Parses the single argument `args[index]`, naming it in the error
+pub fn console_arg<T: std::str::FromStr>(args: &[String], index: usize, name: &str) -> Result<T, String> {
+    let value = args.get(index).ok_or_else(|| format!("missing <{}>", name))?;
+    value.parse().map_err(|_| format!("bad <{}>: '{}'", name, value))
+}
+
+#[derive(Component)]
+struct ConsoleView;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn spawn_console(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+        ConsoleView,
+    )).with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section("", TextStyle {
+                font_size: 20.0,
+                color: Color::rgb(0.6, 1.0, 0.6),
+                ..default()
+            }),
+            ConsoleText,
+        ));
+    });
+}
+
+/// Typing into the open console; the keyboard is swallowed so nothing else reacts to it meanwhile
+fn console_input_system(
+    mut console: ResMut<Console>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut characters: EventReader<ReceivedCharacter>,
+) {
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        console.open = !console.open;
+        keyboard_input.reset(TOGGLE_KEY);
+    }
+    if !console.open {
+        characters.clear();
+        keyboard_events.clear();
+        return;
+    }
+    for event in characters.read() {
+        for c in event.char.chars().filter(|c| !c.is_control()) {
+            console.input.push(c);
+        }
+    }
+    keyboard_events.clear();
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        console.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        console.open = false;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) && !console.history.is_empty() {
+        let index = console.history_index.map_or(console.history.len() - 1, |i| i.saturating_sub(1));
+        console.history_index = Some(index);
+        console.input = console.history[index].clone();
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        if let Some(index) = console.history_index {
+            if index + 1 < console.history.len() {
+                console.history_index = Some(index + 1);
+                console.input = console.history[index + 1].clone();
+            } else {
+                console.history_index = None;
+                console.input.clear();
+            }
+        }
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) {
+        let line = std::mem::take(&mut console.input);
+        if !line.trim().is_empty() {
+            console.history.push(line.clone());
+            console.pending.push(line);
+        }
+        console.history_index = None;
+    }
+    keyboard_input.reset_all();
+}
+
+// Exclusive: commands are one-shot systems and may touch anything in the world
+fn run_console_commands(world: &mut World) {
+    if world.resource::<Console>().pending.is_empty() {
+        return;
+    }
+    let pending = std::mem::take(&mut world.resource_mut::<Console>().pending);
+    for line in pending {
+        let mut words = line.split_whitespace().map(|word| word.to_string());
+        let Some(name) = words.next() else { continue; };
+        let args: Vec<String> = words.collect();
+        let system = world.resource::<ConsoleCommands>().0.get(name.as_str()).map(|command| command.system);
+        let output = match system {
+            Some(system) => {
+                // Replays only hold player input, anything a command changes would be missing from them
+                let in_game = matches!(world.resource::<State<GameState>>().get(), GameState::Game | GameState::Pause);
+                let read_only = matches!(name.as_str(), "help" | "count");
+                let mut recording = world.resource_mut::<Recording>();
+                let stopped = in_game && !read_only && recording.replay.take().is_some();
+                let result = world.run_system_with_input(system, args)
+                    .unwrap_or_else(|e| Err(format!("{:?}", e)));
+                let mut output = match result {
+                    Ok(output) => output,
+                    Err(error) => format!("error: {}", error),
+                };
+                if stopped {
+                    output.push_str("\n(replay recording stopped, console commands are not recorded)");
+                }
+                output
+            }
+            None => format!("unknown command '{}', try 'help'", name),
+        };
+        let mut console = world.resource_mut::<Console>();
+        console.log.push(format!("> {}", line));
+        console.log.extend(output.lines().filter(|line| !line.is_empty()).map(|line| line.to_string()));
+    }
+}
+
+fn console_view_system(
+    console: Res<Console>,
+    mut views: Query<&mut Visibility, With<ConsoleView>>,
+    mut texts: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut visibility in views.iter_mut() {
+        *visibility = if console.open { Visibility::Inherited } else { Visibility::Hidden };
+    }
+    let start = console.log.len().saturating_sub(LOG_LINES);
+    let mut lines: Vec<&str> = console.log[start..].iter().map(|line| line.as_str()).collect();
+    let prompt = format!("> {}_", console.input);
+    lines.push(&prompt);
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+fn help_command(
+    In(_): In<Vec<String>>,
+    commands: Res<ConsoleCommands>,
+) -> ConsoleResult {
+    Ok(commands.0.iter()
+        .map(|(name, command)| format!("{} {} - {}", name, command.args, command.help))
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn state_command(
+    In(args): In<Vec<String>>,
+    players: Query<(), With<Player>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) -> ConsoleResult {
+    let name: String = console_arg(&args, 0, "name")?;
+    let state = match name.as_str() {
+        "menu" => GameState::Menu,
+        "loading" => GameState::Loading,
+        "game" => GameState::Game,
+        "pause" => GameState::Pause,
+        "settings" => GameState::Settings,
+        "gameover" => GameState::GameOver,
+        "replays" => GameState::Replays,
+        _ => return Err(format!("unknown state '{}'", name)),
+    };
+    // Game and Pause expect everything Loading spawns to be there already
+    let state = match state {
+        GameState::Game if players.is_empty() => GameState::Loading,
+        GameState::Pause if players.is_empty() => return Err("no game running to pause".to_string()),
+        state => state,
+    };
+    game_state.set(state);
+    Ok(format!("switching to {:?}", state))
+}
+
+/// Groups entities by which of this crate's components they have, e.g. "Enemy Hitbox Pingable: 5"
+fn count_command(
+    In(_): In<Vec<String>>,
+    world: &mut World,
+) -> ConsoleResult {
+    let crate_prefix = concat!(env!("CARGO_CRATE_NAME"), "::");
+    let mut groups: BTreeMap<String, usize> = BTreeMap::new();
+    let mut total = 0;
+    for archetype in world.archetypes().iter().filter(|archetype| !archetype.is_empty()) {
+        let mut names: Vec<&str> = archetype.components()
+            .filter_map(|id| world.components().get_info(id))
+            .map(|info| info.name())
+            .filter(|name| name.starts_with(crate_prefix))
+            .map(|name| name.rsplit("::").next().unwrap_or(name))
+            .collect();
+        names.sort();
+        let key = if names.is_empty() { "(engine)".to_string() } else { names.join(" ") };
+        *groups.entry(key).or_default() += archetype.len();
+        total += archetype.len();
+    }
+    let mut lines: Vec<(String, usize)> = groups.into_iter().collect();
+    lines.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let mut output = format!("{} entities", total);
+    for (key, count) in lines {
+        output.push_str(&format!("\n{:>5}  {}", count, key));
+    }
+    Ok(output)
+}
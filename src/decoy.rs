@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use crate::gamestate::GameState;
+use crate::gamestate::despawn_system;
+use crate::gamestate::GameDespawnable;
+use crate::player::Player;
+use crate::actions::Action;
+use crate::actions::ActionState;
+use crate::sonar::Pingable;
+use crate::sonar::Signature;
+use crate::hitbox::Hitbox;
+use crate::torpedo::PlayerTorpedo;
+use crate::torpedo::GuidedTorpedo;
+use crate::torpedo::player_shoot_torpedo_system;
+use crate::torpedo::GUIDED_RANGE;
+use crate::simulation::SimulationSet;
+use std::time::Duration;
+
+/// Noisemakers both sides drop to pull guided torpedoes off them
+pub struct DecoyPlugin;
+impl Plugin for DecoyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, launch_decoy_system.in_set(SimulationSet::Ai).after(player_shoot_torpedo_system).run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, decoy_drift_system.in_set(SimulationSet::Movement).run_if(in_state(GameState::Game)))
+            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>);
+    }
+}
+
+/// How much louder a decoy sounds to a seeker than a boat
+pub const DECOY_LOUDNESS: f32 = 3.0;
+/// Seconds a decoy keeps making noise
+const DECOY_LIFETIME: f32 = 12.0;
+/// Units per second a decoy drifts astern of whoever dropped it
+const DECOY_DRIFT: f32 = 4.0;
+/// Seconds between two decoys from the same boat
+const DECOY_RELOAD: f32 = 8.0;
+
+#[derive(Component)]
+pub struct Decoy {
+    velocity: Vec2,
+    lifetime: Timer,
+}
+
+#[derive(Component)]
+pub struct PlayerDecoy;
+
+#[derive(Component)]
+pub struct EnemyDecoy;
+
+/// Lets a boat drop decoys, the player when asked to and enemies when a guided torpedo comes within seeker range
+#[derive(Component)]
+pub struct DecoyLauncher {
+    reload: Timer,
+}
+
+impl Default for DecoyLauncher {
+    /// Loaded from the start
+    fn default() -> DecoyLauncher {
+        let mut reload = Timer::from_seconds(DECOY_RELOAD, TimerMode::Once);
+        reload.set_elapsed(reload.duration());
+        DecoyLauncher { reload }
+    }
+}
+
+pub fn spawn_decoy(
+    commands: &mut Commands,
+    from: &Transform,
+    player: bool,
+) {
+    let velocity = -from.up().xy() * DECOY_DRIFT;
+    let mut decoy = commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(1.0, 0.8, 0.2),
+                custom_size: Some(Vec2::splat(8.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(from.translation),
+            ..default()
+        },
+        Decoy {
+            velocity,
+            lifetime: Timer::from_seconds(DECOY_LIFETIME, TimerMode::Once),
+        },
+        Hitbox::new(10.0, 10.0),
+        Pingable::default(),
+        Signature::DECOY,
+        GameDespawnable,
+    ));
+    if player {
+        decoy.insert(PlayerDecoy);
+    } else {
+        decoy.insert(EnemyDecoy);
+    }
+}
+
+fn launch_decoy_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    action_state: Res<ActionState>,
+    mut launchers: Query<(&mut DecoyLauncher, &Transform, Has<Player>)>,
+    // Only seekers listen for decoys, dropping one for anything else would waste it
+    player_torpedoes: Query<&Transform, (With<PlayerTorpedo>, With<GuidedTorpedo>)>,
+) {
+    for (mut launcher, transform, player) in launchers.iter_mut() {
+        launcher.reload.tick(Duration::from_secs_f32(time.delta_seconds()));
+        if !launcher.reload.finished() {
+            continue;
+        }
+        let launch = if player {
+            action_state.tick_just_pressed(Action::DeployDecoy)
+        } else {
+            player_torpedoes.iter().any(|torpedo| torpedo.translation.distance(transform.translation) <= GUIDED_RANGE)
+        };
+        if launch {
+            spawn_decoy(&mut commands, transform, player);
+            launcher.reload.reset();
+        }
+    }
+}
+
+fn decoy_drift_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Decoy, &mut Transform)>,
+) {
+    for (entity, mut decoy, mut transform) in query.iter_mut() {
+        transform.translation += (decoy.velocity * time.delta_seconds()).extend(0.0);
+        if decoy.lifetime.tick(Duration::from_secs_f32(time.delta_seconds())).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
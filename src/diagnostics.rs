@@ -0,0 +1,149 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use bevy::prelude::*;
+use bevy::app::AppExit;
+use bevy::diagnostic::Diagnostic;
+use bevy::diagnostic::DiagnosticPath;
+use bevy::diagnostic::Diagnostics;
+use bevy::diagnostic::DiagnosticsStore;
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
+use bevy::diagnostic::RegisterDiagnostic;
+use bevy::core::FrameCount;
+use crate::enemy::Enemy;
+use crate::sonar::Pingable;
+use crate::torpedo::Torpedo;
+
+pub struct DiagnosticsHudPlugin;
+impl Plugin for DiagnosticsHudPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        for path in COUNTS.iter() {
+            app.register_diagnostic(Diagnostic::new(path.clone()));
+        }
+        for path in TIMINGS.iter() {
+            app.register_diagnostic(Diagnostic::new(path.clone()).with_suffix("ms"));
+        }
+        app.add_systems(Startup, spawn_diagnostics_hud)
+            .add_systems(Update, (entity_count_system, diagnostics_hud_system).chain())
+            .add_systems(Last, write_csv_system.run_if(resource_exists::<DiagnosticsCsv>));
+    }
+}
+
+const TOGGLE_KEY: KeyCode = KeyCode::F7;
+
+pub const TORPEDO_COUNT: DiagnosticPath = DiagnosticPath::const_new("entities/torpedoes");
+pub const ENEMY_COUNT: DiagnosticPath = DiagnosticPath::const_new("entities/enemies");
+pub const PINGABLE_COUNT: DiagnosticPath = DiagnosticPath::const_new("entities/pingables");
+/// Hitbox pairs `collision_system` tested in its last run
+pub const COLLISION_PAIRS: DiagnosticPath = DiagnosticPath::const_new("collision/pairs");
+pub const COLLISION_SYSTEM_TIME: DiagnosticPath = DiagnosticPath::const_new("systems/collision_system");
+pub const MOVE_TORPEDO_SYSTEM_TIME: DiagnosticPath = DiagnosticPath::const_new("systems/move_torpedo_system");
+pub const FADE_AWAY_SYSTEM_TIME: DiagnosticPath = DiagnosticPath::const_new("systems/fade_away_system");
+
+static COUNTS: [DiagnosticPath; 4] = [TORPEDO_COUNT, ENEMY_COUNT, PINGABLE_COUNT, COLLISION_PAIRS];
+static TIMINGS: [DiagnosticPath; 3] = [COLLISION_SYSTEM_TIME, MOVE_TORPEDO_SYSTEM_TIME, FADE_AWAY_SYSTEM_TIME];
+
+/// Every frame's numbers as one CSV row, see `--diagnostics-csv`
+#[derive(Resource)]
+pub struct DiagnosticsCsv(BufWriter<File>);
+
+impl DiagnosticsCsv {
+    pub fn create(path: &Path) -> std::io::Result<DiagnosticsCsv> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let mut header = vec!["frame", "fps", "frame_time_ms"];
+        header.extend(COUNTS.iter().chain(TIMINGS.iter()).map(|path| path.as_str()));
+        writeln!(writer, "{}", header.join(","))?;
+        Ok(DiagnosticsCsv(writer))
+    }
+}
+
+#[derive(Component)]
+struct DiagnosticsHud;
+
+fn spawn_diagnostics_hud(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        TextBundle {
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section("", TextStyle {
+                font_size: 18.0,
+                color: Color::YELLOW,
+                ..default()
+            }).with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                right: Val::Px(8.0),
+                ..default()
+            }).with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6))
+        },
+        DiagnosticsHud,
+    ));
+}
+
+fn entity_count_system(
+    mut diagnostics: Diagnostics,
+    torpedoes: Query<(), With<Torpedo>>,
+    enemies: Query<(), With<Enemy>>,
+    pingables: Query<(), With<Pingable>>,
+) {
+    diagnostics.add_measurement(&TORPEDO_COUNT, || torpedoes.iter().count() as f64);
+    diagnostics.add_measurement(&ENEMY_COUNT, || enemies.iter().count() as f64);
+    diagnostics.add_measurement(&PINGABLE_COUNT, || pingables.iter().count() as f64);
+}
+
+fn value(store: &DiagnosticsStore, path: &DiagnosticPath) -> f64 {
+    store.get(path).and_then(|diagnostic| diagnostic.value()).unwrap_or(0.0)
+}
+
+fn diagnostics_hud_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    store: Res<DiagnosticsStore>,
+    mut hud: Query<(&mut Text, &mut Visibility), With<DiagnosticsHud>>,
+) {
+    for (mut text, mut visibility) in hud.iter_mut() {
+        if keyboard_input.just_pressed(TOGGLE_KEY) {
+            *visibility = if *visibility == Visibility::Hidden { Visibility::Inherited } else { Visibility::Hidden };
+        }
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        let fps = store.get(&FrameTimeDiagnosticsPlugin::FPS).and_then(|fps| fps.smoothed()).unwrap_or(0.0);
+        let mut lines = vec![format!("fps {:.0}", fps)];
+        lines.extend(COUNTS.iter().map(|path| format!("{} {:.0}", path.as_str(), value(&store, path))));
+        // Averaged over the history, a single tick is too noisy to read
+        lines.extend(TIMINGS.iter().map(|path| {
+            let average = store.get(path).and_then(|diagnostic| diagnostic.average()).unwrap_or(0.0);
+            format!("{} {:.3}ms", path.as_str(), average)
+        }));
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+fn write_csv_system(
+    mut commands: Commands,
+    mut csv: ResMut<DiagnosticsCsv>,
+    store: Res<DiagnosticsStore>,
+    frame: Res<FrameCount>,
+    mut app_exit_events: EventReader<AppExit>,
+) {
+    let mut row = vec![
+        frame.0.to_string(),
+        format!("{:.1}", value(&store, &FrameTimeDiagnosticsPlugin::FPS)),
+        format!("{:.3}", value(&store, &FrameTimeDiagnosticsPlugin::FRAME_TIME)),
+    ];
+    row.extend(COUNTS.iter().map(|path| format!("{:.0}", value(&store, path))));
+    row.extend(TIMINGS.iter().map(|path| format!("{:.4}", value(&store, path))));
+    let mut result = writeln!(csv.0, "{}", row.join(","));
+    if app_exit_events.read().count() > 0 {
+        result = result.and_then(|_| csv.0.flush());
+    }
+    if let Err(e) = result {
+        warn!("Could not write diagnostics, stopping: {}", e);
+        commands.remove_resource::<DiagnosticsCsv>();
+    }
+}
@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use bevy::asset::io::AssetSource;
+use bevy::asset::io::AssetSourceId;
+use bevy::asset::io::memory::Dir;
+use bevy::asset::io::memory::MemoryAssetReader;
+use std::path::Path;
+
+/// Compiles the listed files from `assets/` into the binary, under the same relative paths
+macro_rules! embed {
+    ($dir:expr, $($path:literal),* $(,)?) => {
+        $(
+            $dir.insert_asset(
+                Path::new($path),
+                &include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $path))[..],
+            );
+        )*
+    };
+}
+
+/// Serves every asset from memory instead of the assets folder.
+/// Has to run before `DefaultPlugins`, as `AssetPlugin` picks up its sources when it's built.
+pub fn register_embedded_assets(app: &mut App) {
+    let dir = Dir::default();
+    embed!(dir,
+        "menu-inicial.png",
+        "submarino.png",
+        "heart.png",
+        "radar.png",
+        "painel.png",
+        "enemy.png",
+        "torpedo-comum.png",
+        "torpedo-teleguiado.png",
+        "torpedo-contramedida.png",
+        "sounds/ping.wav",
+        "sounds/launch.wav",
+        "sounds/explosion.wav",
+        "sounds/ambience.wav",
+    );
+    app.register_asset_source(
+        AssetSourceId::Default,
+        AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+    );
+}
@@ -1,29 +1,45 @@
 use bevy::prelude::*;
-use bevy::window::*;
 use bevy::math::bounding::RayCast2d;
 use bevy::math::bounding::BoundingCircle;
 use crate::gamestate::GameState;
 use crate::gamestate::despawn_system;
 use crate::gamestate::GameDespawnable;
+use crate::gamestate::PlayArea;
 use crate::player::Player;
 use crate::sonar::Sonar;
 use crate::sonar::Pingable;
+use crate::sonar::Signature;
+use crate::decoy::DecoyLauncher;
 use crate::hitbox::Hitbox;
 use crate::torpedo::FireRegularTorpedo;
+use crate::torpedo::FireGuidedTorpedo;
 use crate::torpedo::EnemyDamageEvent;
+use crate::settings::Difficulty;
+use crate::settings::Settings;
+use crate::loading::GameAssets;
+use crate::loading::RequestAssets;
+use crate::simulation::GameRng;
+use crate::simulation::SimulationSet;
+use crate::torpedo::TorpedoCollisions;
+use crate::console::ConsoleCommandsExt;
+use crate::console::ConsoleResult;
+use crate::console::console_arg;
 use rand::Rng;
 
 pub struct EnemyPlugin;
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Loading), spawn_enemy)
-           .add_systems(Update, enemy_movement_system.run_if(in_state(GameState::Game)))
-           .add_systems(Update, enemy_rotation_system.run_if(in_state(GameState::Game)))
-           .add_systems(Update, enemy_destination_system.run_if(in_state(GameState::Game)))
-           .add_systems(Update, enemy_fire_system.run_if(in_state(GameState::Game)))
-           .add_systems(Update, enemy_damage_system.run_if(in_state(GameState::Game)))
+        app.add_systems(OnEnter(GameState::Loading), spawn_enemy.after(RequestAssets))
+           .add_systems(FixedUpdate, enemy_destination_system.in_set(SimulationSet::Ai).run_if(in_state(GameState::Game)))
+           .add_systems(FixedUpdate, enemy_fire_system.in_set(SimulationSet::Ai).run_if(in_state(GameState::Game)))
+           .add_systems(FixedUpdate, enemy_rotation_system.in_set(SimulationSet::Movement).run_if(in_state(GameState::Game)))
+           .add_systems(FixedUpdate, enemy_movement_system.in_set(SimulationSet::Movement).after(enemy_rotation_system).run_if(in_state(GameState::Game)))
+           .add_systems(FixedUpdate, enemy_damage_system.in_set(SimulationSet::Damage).after(TorpedoCollisions).run_if(in_state(GameState::Game)))
+           .add_systems(FixedUpdate, next_wave_system.in_set(SimulationSet::Damage).after(enemy_damage_system).run_if(in_state(GameState::Game)))
            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>)
-           .insert_resource(EnemyPositions::default());
+           .add_console_command("spawn", "<archetype> <x> <y>", "Spawn an enemy at a position relative to the player, archetypes: sub", spawn_command)
+           .insert_resource(EnemyPositions::default())
+           .init_resource::<Wave>();
         }
 }
 
@@ -34,6 +50,15 @@ pub struct Enemy {
     destination: Vec3,
     state: EnemyState,
     life: i32,
+    /// Torpedoes fired so far, see `Difficulty::guided_every`
+    shots: u32,
+}
+
+impl Enemy {
+    /// Where the enemy is heading
+    pub fn destination(&self) -> Vec3 {
+        self.destination
+    }
 }
 
 #[derive(Default, Resource)]
@@ -41,6 +66,30 @@ pub struct EnemyPositions {
     pub positions: Vec<Vec3>,
 }
 
+/// Enemies come in waves, the next one as soon as the last boat of the current one sinks
+#[derive(Resource)]
+pub struct Wave {
+    /// Wave every game starts at
+    pub start: u32,
+    pub current: u32,
+}
+
+impl Default for Wave {
+    fn default() -> Wave {
+        Wave {
+            start: 1,
+            current: 1,
+        }
+    }
+}
+
+impl Wave {
+    /// Each wave brings one boat more than the last
+    pub fn size(&self, difficulty: Difficulty) -> usize {
+        difficulty.enemy_count() + self.current.saturating_sub(1) as usize
+    }
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
 pub enum EnemyState {
     Roaming,
@@ -49,54 +98,114 @@ pub enum EnemyState {
 
 pub fn spawn_enemy(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    assets: Res<GameAssets>,
+    settings: Res<Settings>,
+    play_area: Res<PlayArea>,
+    mut rng: ResMut<GameRng>,
+    mut wave: ResMut<Wave>,
+) {
+    wave.current = wave.start;
+    spawn_wave(&mut commands, &assets, &play_area, &mut rng, wave.size(settings.difficulty));
+}
+
+pub fn next_wave_system(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    settings: Res<Settings>,
+    play_area: Res<PlayArea>,
+    mut rng: ResMut<GameRng>,
+    mut wave: ResMut<Wave>,
+    enemies: Query<(), With<Enemy>>,
 ) {
-    let window = windows.single_mut();
-    let radius = (window.resolution.height() / 4.0) + 20.0;
-    let mut rng = rand::thread_rng();
+    if !enemies.is_empty() {
+        return;
+    }
+    wave.current += 1;
+    info!("Wave {}", wave.current);
+    spawn_wave(&mut commands, &assets, &play_area, &mut rng, wave.size(settings.difficulty));
+}
+
+fn spawn_wave(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    play_area: &PlayArea,
+    rng: &mut GameRng,
+    count: usize,
+) {
+    let radius = (play_area.height / 4.0) + 20.0;
 
-    for _ in 0..5 {
+    for _ in 0..count {
         let angle = rng.gen_range(0.0..std::f32::consts::TAU);
         let distance = rng.gen_range(radius..radius + 200.0);
         let position = Vec3::new(distance * angle.cos(), distance * angle.sin(), 0.0);
-        let direction_to_player = Vec3::new(0.0, 0.0, 0.0) - position;
-        let angle_to_player = direction_to_player.y.atan2(direction_to_player.x);
-
-        commands.spawn((
-            SpriteBundle {
-                texture: asset_server.load("../assets/enemy.png"),
-                transform: Transform {
-                    translation: position,
-                    rotation: Quat::from_rotation_z(angle_to_player - std::f32::consts::FRAC_PI_2),
-                    scale: Vec3::splat(0.1),
-                }, 
-                ..default()
-            },
-            Enemy {
-                rotation_speed: (0.4/1.5),//rng.gen_range(0.5..2.0),
-                movement_speed: (25.0/2.0),
-                destination: Vec3::ZERO, //this will be set by enemy_rotation_system()
-                state: EnemyState::Roaming,
-                life: 1,
-            },
-            Hitbox::new(30.0, 90.0),
-            Pingable::default(),
-            GameDespawnable,
-        ));
+        spawn_enemy_at(commands, assets, position);
+    }
+}
+
+/// A roaming sub at `position`, facing the player
+pub fn spawn_enemy_at(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    position: Vec3,
+) {
+    let direction_to_player = Vec3::new(0.0, 0.0, 0.0) - position;
+    let angle_to_player = direction_to_player.y.atan2(direction_to_player.x);
+
+    commands.spawn((
+        SpriteBundle {
+            texture: assets.enemy.clone(),
+            transform: Transform {
+                translation: position,
+                rotation: Quat::from_rotation_z(angle_to_player - std::f32::consts::FRAC_PI_2),
+                scale: Vec3::splat(0.1),
+            }, 
+            ..default()
+        },
+        Enemy {
+            rotation_speed: (0.4/1.5),//rng.gen_range(0.5..2.0),
+            movement_speed: (25.0/2.0),
+            destination: Vec3::ZERO, //this will be set by enemy_rotation_system()
+            state: EnemyState::Roaming,
+            life: 1,
+            shots: 0,
+        },
+        Hitbox::new(30.0, 90.0),
+        Pingable::default(),
+        Signature::ENEMY_SUB,
+        DecoyLauncher::default(),
+        GameDespawnable,
+    ));
+}
+
+fn spawn_command(
+    In(args): In<Vec<String>>,
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    state: Res<State<GameState>>,
+) -> ConsoleResult {
+    let archetype: String = console_arg(&args, 0, "archetype")?;
+    let x: f32 = console_arg(&args, 1, "x")?;
+    let y: f32 = console_arg(&args, 2, "y")?;
+    if !matches!(state.get(), GameState::Game | GameState::Pause) {
+        return Err("enemies can only be spawned during a game".to_string());
     }
+    match archetype.as_str() {
+        "sub" => spawn_enemy_at(&mut commands, &assets, Vec3::new(x, y, 0.0)),
+        _ => return Err(format!("unknown archetype '{}'", archetype)),
+    }
+    Ok(format!("spawned {} at {} {}", archetype, x, y))
 }
 
 fn enemy_destination_system(
     mut enemies_query: Query<(&mut Enemy, &mut Transform)>,
     mut sonar_query: Query<(&Sonar, &Transform), Without<Enemy>>,
+    mut rng: ResMut<GameRng>,
 ) {
     let (sonar, sonar_transform) = sonar_query.single_mut();
     let max_distance = sonar.radius;
     let min_distance = 185.0;
     let center = sonar_transform.translation;
 
-    let mut rng = rand::thread_rng();
     for (mut enemy, transform) in enemies_query.iter_mut() {
         if enemy.state != EnemyState::Roaming {
             continue;
@@ -173,11 +282,14 @@ fn enemy_movement_system(
     }
 }
 
-fn enemy_fire_system(
+pub fn enemy_fire_system(
     mut event_writer: EventWriter<FireRegularTorpedo>,
+    mut guided_writer: EventWriter<FireGuidedTorpedo>,
     mut query: Query<(&mut Enemy, &Transform)>,
     player_query: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    settings: Res<Settings>,
 ) {
+    let guided_every = settings.difficulty.guided_every();
     for (mut enemy, transform) in query.iter_mut() {
         if enemy.state != EnemyState::Attacking {
             continue;
@@ -187,7 +299,12 @@ fn enemy_fire_system(
         let up = transform.up().xy();
         let up_dot = up.dot(to_target);
         if (up_dot - 1.0).abs() < f32::EPSILON {
-            event_writer.send(FireRegularTorpedo { from: transform.translation.xy(), towards: to_target });
+            if guided_every.is_some_and(|every| enemy.shots % every == every - 1) {
+                guided_writer.send(FireGuidedTorpedo { from: transform.translation.xy(), towards: to_target });
+            } else {
+                event_writer.send(FireRegularTorpedo { from: transform.translation.xy(), towards: to_target });
+            }
+            enemy.shots += 1;
             enemy.state = EnemyState::Roaming;
         }
     }
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use crate::gamestate::GameState;
+use crate::gamestate::despawn_system;
+use crate::gamestate::GameDespawnable;
+use crate::player::Player;
+use crate::player::PlayerHitEvent;
+use rand::Rng;
+use std::time::Duration;
+
+pub struct FeedbackPlugin;
+impl Plugin for FeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, hit_feedback_system.run_if(in_state(GameState::Game)))
+            .add_systems(Update, camera_shake_system.run_if(in_state(GameState::Game)))
+            .add_systems(Update, damage_indicator_system.run_if(in_state(GameState::Game)))
+            .add_systems(OnEnter(GameState::GameOver), reset_camera_shake)
+            .add_systems(OnEnter(GameState::Menu), (despawn_system::<GameDespawnable>, reset_camera_shake));
+    }
+}
+
+#[derive(Component, Default)]
+pub struct CameraShake {
+    trauma: f32,
+}
+
+#[derive(Component)]
+struct DamageIndicator {
+    timer: Timer,
+}
+
+const SHAKE_DECAY: f32 = 1.5;
+const SHAKE_MAX_OFFSET: f32 = 12.0;
+const INDICATOR_DISTANCE: f32 = 90.0;
+const INDICATOR_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+
+fn hit_feedback_system(
+    mut commands: Commands,
+    mut hit_events: EventReader<PlayerHitEvent>,
+    mut camera_query: Query<&mut CameraShake>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    for hit in hit_events.read() {
+        for mut shake in camera_query.iter_mut() {
+            shake.trauma = (shake.trauma + 0.4 * hit.damage as f32).min(1.0);
+        }
+        let Ok(player_transform) = player_query.get_single() else { continue; };
+        if hit.direction == Vec2::ZERO {
+            continue;
+        }
+        let position = player_transform.translation.xy() + hit.direction * INDICATOR_DISTANCE;
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: INDICATOR_COLOR,
+                    custom_size: Some(Vec2::new(40.0, 6.0)),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: position.extend(1.0),
+                    rotation: Quat::from_rotation_z(hit.direction.to_angle() - std::f32::consts::FRAC_PI_2),
+                    ..default()
+                },
+                ..default()
+            },
+            DamageIndicator {
+                timer: Timer::new(Duration::from_millis(1000), TimerMode::Once),
+            },
+            GameDespawnable,
+        ));
+    }
+}
+
+fn camera_shake_system(
+    time: Res<Time>,
+    mut camera_query: Query<(&mut CameraShake, &mut Transform)>,
+) {
+    // Purely cosmetic, so it stays off GameRng and never shifts the gameplay sequence
+    let mut rng = rand::thread_rng();
+    for (mut shake, mut transform) in camera_query.iter_mut() {
+        shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_seconds()).max(0.0);
+        let intensity = shake.trauma * shake.trauma * SHAKE_MAX_OFFSET;
+        transform.translation.x = rng.gen_range(-1.0..=1.0) * intensity;
+        transform.translation.y = rng.gen_range(-1.0..=1.0) * intensity;
+    }
+}
+
+fn reset_camera_shake(
+    mut camera_query: Query<(&mut CameraShake, &mut Transform)>,
+) {
+    for (mut shake, mut transform) in camera_query.iter_mut() {
+        shake.trauma = 0.0;
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+    }
+}
+
+fn damage_indicator_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut DamageIndicator, &mut Sprite)>,
+) {
+    for (entity, mut indicator, mut sprite) in query.iter_mut() {
+        indicator.timer.tick(time.delta());
+        if indicator.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        sprite.color.set_a(indicator.timer.fraction_remaining());
+    }
+}
@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+use crate::gamestate::GameState;
+use crate::player::Player;
+use crate::tracking::Tracks;
+use crate::torpedo::PLAYER_TORPEDO_SPEED;
+use crate::torpedo::player_shoot_torpedo_system;
+use crate::simulation::SimulationSet;
+use crate::simulation::SimulationTick;
+use crate::simulation::heading;
+
+pub struct FireControlPlugin;
+impl Plugin for FireControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FiringSolution>()
+            .add_systems(OnEnter(GameState::Loading), reset_solution)
+            .add_systems(FixedUpdate, firing_solution_system.in_set(SimulationSet::Ai).before(player_shoot_torpedo_system).run_if(in_state(GameState::Game)));
+    }
+}
+
+/// Where to aim at the designated track so a torpedo meets it, `None` without a designation or a solution
+#[derive(Resource, Default, Deref)]
+pub struct FiringSolution(pub Option<Solution>);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Solution {
+    pub track: u32,
+    /// Heading to launch at, in the same radians as a sprite's rotation
+    pub bearing: f32,
+    /// How far `bearing` leads the line of sight to the target, positive is counter-clockwise
+    pub lead_angle: f32,
+    /// Seconds from launch to impact
+    pub time_to_intercept: f32,
+    pub intercept: Vec2,
+}
+
+/// Earliest time a projectile from `from` at `speed` can meet a target at `target` moving at `velocity`,
+/// or `None` if the target outruns it
+pub fn intercept_time(from: Vec2, target: Vec2, velocity: Vec2, speed: f32) -> Option<f32> {
+    // |offset + velocity t| = speed t, a quadratic in t
+    let offset = target - from;
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2.0 * offset.dot(velocity);
+    let c = offset.length_squared();
+    if a.abs() < 1e-6 {
+        // Same speed as the target: only a target closing in can be caught
+        return if b < 0.0 { Some(-c / b) } else { None };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+        .into_iter()
+        .filter(|t| *t >= 0.0)
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+pub fn solve(track: u32, from: Vec2, target: Vec2, velocity: Vec2, speed: f32) -> Option<Solution> {
+    let time_to_intercept = intercept_time(from, target, velocity, speed)?;
+    let intercept = target + velocity * time_to_intercept;
+    let line_of_sight = heading(target - from);
+    let bearing = heading(intercept - from);
+    let lead_angle = (bearing - line_of_sight + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+    Some(Solution { track, bearing, lead_angle, time_to_intercept, intercept })
+}
+
+fn reset_solution(
+    mut solution: ResMut<FiringSolution>,
+) {
+    solution.0 = None;
+}
+
+fn firing_solution_system(
+    tracks: Res<Tracks>,
+    tick: Res<SimulationTick>,
+    player_query: Query<&Transform, With<Player>>,
+    mut solution: ResMut<FiringSolution>,
+) {
+    let (Some(track), Ok(player_transform)) = (tracks.designated(), player_query.get_single()) else {
+        solution.0 = None;
+        return;
+    };
+    let from = player_transform.translation.xy();
+    solution.0 = solve(track.id, from, track.estimated_position(tick.0), track.velocity, PLAYER_TORPEDO_SPEED);
+}
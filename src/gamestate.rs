@@ -7,7 +7,26 @@ pub enum GameState {
     Loading,
     Game,
     Pause,
-    GameOver
+    Settings,
+    GameOver,
+    Replays,
+}
+
+/// Logical size of the game world, independent of the window it is shown in.
+/// layout_system zooms the camera so this area fits whatever window we have.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PlayArea {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for PlayArea {
+    fn default() -> PlayArea {
+        PlayArea {
+            width: 1920.0,
+            height: 1080.0,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -25,6 +44,12 @@ pub struct PauseDespawnable;
 #[derive(Component)]
 pub struct LoadingDespawnable;
 
+#[derive(Component)]
+pub struct SettingsDespawnable;
+
+#[derive(Component)]
+pub struct ReplaysDespawnable;
+
 pub fn despawn_system<T: Component>(
     mut commands: Commands,
     query: Query<Entity, With<T>>,
@@ -1,17 +1,16 @@
 use bevy::prelude::*;
-use bevy::sprite::MaterialMesh2dBundle;
 use crate::gamestate::GameState;
+use crate::simulation::SimulationSet;
+use crate::diagnostics::COLLISION_PAIRS;
+use crate::diagnostics::COLLISION_SYSTEM_TIME;
+use bevy::diagnostic::Diagnostics;
+use bevy::utils::Instant;
 
 pub struct HitboxPlugin;
 impl Plugin for HitboxPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<Collision>()
-            .add_systems(Update, invulnerable_after_spawn_system.run_if(in_state(GameState::Game)))
-            .add_systems(Update, collision_system.run_if(in_state(GameState::Game)))
-            .add_systems(Update, collide_system.run_if(in_state(GameState::Game)));
-
-        #[cfg(feature = "debug")]
-        app.add_systems(Update, handle_debugbox_system.run_if(in_state(GameState::Game)));
+            .add_systems(FixedUpdate, (invulnerable_after_spawn_system, collision_system, collide_system).chain().in_set(SimulationSet::Collision).run_if(in_state(GameState::Game)));
             //.add_systems(Update, read_event_debug_system.run_if(in_state(GameState::Game)));
     }
 }
@@ -23,9 +22,6 @@ pub struct Hitbox {
     pub colliding: bool,
 }
 
-#[derive(Component)]
-struct Debugbox;
-
 #[derive(Component)]
 pub struct InvulnerableAfterSpawn;
 
@@ -40,127 +36,53 @@ impl Hitbox {
         Hitbox { width: w, height: h, colliding: false }
     }
 
+    /// Axis-aligned box around the rotated hitbox, which is what collisions are tested against
+    pub fn aabb(&self, transform: &Transform) -> Rect {
+        let (_, angle) = transform.rotation.to_axis_angle();
+        let corners = [
+            Vec2::new(-self.width / 2.0, -self.height / 2.0),
+            Vec2::new( self.width / 2.0, -self.height / 2.0),
+            Vec2::new(-self.width / 2.0,  self.height / 2.0),
+            Vec2::new( self.width / 2.0,  self.height / 2.0),
+        ];
+        let rotated = corners.map(|v| Vec2::new(
+            v.x * angle.cos() - v.y * angle.sin(),
+            v.y * angle.cos() + v.x * angle.sin(),
+        ));
+        let min = rotated.iter().copied().reduce(Vec2::min).unwrap() + transform.translation.xy();
+        let max = rotated.iter().copied().reduce(Vec2::max).unwrap() + transform.translation.xy();
+        Rect { min, max }
+    }
+
     pub fn aabb_intersects(
         hitbox_a: &Hitbox, transform_a: &Transform,
         hitbox_b: &Hitbox, transform_b: &Transform,
-        debug: Option<(&mut Commands, &mut ResMut<Assets<Mesh>>, &mut ResMut<Assets<ColorMaterial>>)>,
     ) -> bool {
-        let a_min_x = - hitbox_a.width  / 2.0;
-        let a_max_x =   hitbox_a.width  / 2.0;
-        let a_min_y = - hitbox_a.height / 2.0;
-        let a_max_y =   hitbox_a.height / 2.0;
-
-        let b_min_x = - hitbox_b.width  / 2.0;
-        let b_max_x =   hitbox_b.width  / 2.0;
-        let b_min_y = - hitbox_b.height / 2.0;
-        let b_max_y =   hitbox_b.height / 2.0;
-
-        let vertices = (
-            Vec2::new(a_min_x, a_min_y),
-            Vec2::new(a_max_x, a_min_y),
-            Vec2::new(a_min_x, a_max_y),
-            Vec2::new(a_max_x, a_max_y),
-
-            Vec2::new(b_min_x, b_min_y),
-            Vec2::new(b_max_x, b_min_y),
-            Vec2::new(b_min_x, b_max_y),
-            Vec2::new(b_max_x, b_max_y),
-        );
-
-        let (_, angle_a) = transform_a.rotation.to_axis_angle();
-        let (_, angle_b) = transform_b.rotation.to_axis_angle();
-
-        let rotated_vertices_a = vec![
-            Vec2::new(
-                vertices.0.x * angle_a.cos() - vertices.0.y * angle_a.sin(),
-                vertices.0.y * angle_a.cos() + vertices.0.x * angle_a.sin()),
-            Vec2::new(
-                vertices.1.x * angle_a.cos() - vertices.1.y * angle_a.sin(),
-                vertices.1.y * angle_a.cos() + vertices.1.x * angle_a.sin()),
-            Vec2::new(
-                vertices.2.x * angle_a.cos() - vertices.2.y * angle_a.sin(),
-                vertices.2.y * angle_a.cos() + vertices.2.x * angle_a.sin()),
-            Vec2::new(
-                vertices.3.x * angle_a.cos() - vertices.3.y * angle_a.sin(),
-                vertices.3.y * angle_a.cos() + vertices.3.x * angle_a.sin()),
-        ];
-        let rotated_vertices_b = vec![
-            Vec2::new(
-                vertices.4.x * angle_b.cos() - vertices.4.y * angle_b.sin(),
-                vertices.4.y * angle_b.cos() + vertices.4.x * angle_b.sin()),
-            Vec2::new(
-                vertices.5.x * angle_b.cos() - vertices.5.y * angle_b.sin(),
-                vertices.5.y * angle_b.cos() + vertices.5.x * angle_b.sin()),
-            Vec2::new(
-                vertices.6.x * angle_b.cos() - vertices.6.y * angle_b.sin(),
-                vertices.6.y * angle_b.cos() + vertices.6.x * angle_b.sin()),
-            Vec2::new(
-                vertices.7.x * angle_b.cos() - vertices.7.y * angle_b.sin(),
-                vertices.7.y * angle_b.cos() + vertices.7.x * angle_b.sin()),
-        ];
-
-        let a_min_x = rotated_vertices_a.iter().map(|v| v.x).reduce(f32::min).unwrap() + transform_a.translation.x;
-        let a_max_x = rotated_vertices_a.iter().map(|v| v.x).reduce(f32::max).unwrap() + transform_a.translation.x;
-        let a_min_y = rotated_vertices_a.iter().map(|v| v.y).reduce(f32::min).unwrap() + transform_a.translation.y;
-        let a_max_y = rotated_vertices_a.iter().map(|v| v.y).reduce(f32::max).unwrap() + transform_a.translation.y;
-
-        let b_min_x = rotated_vertices_b.iter().map(|v| v.x).reduce(f32::min).unwrap() + transform_b.translation.x;
-        let b_max_x = rotated_vertices_b.iter().map(|v| v.x).reduce(f32::max).unwrap() + transform_b.translation.x;
-        let b_min_y = rotated_vertices_b.iter().map(|v| v.y).reduce(f32::min).unwrap() + transform_b.translation.y;
-        let b_max_y = rotated_vertices_b.iter().map(|v| v.y).reduce(f32::max).unwrap() + transform_b.translation.y;
-
-        if let Some((commands, meshes, materials)) = debug {
-            commands.spawn((
-                MaterialMesh2dBundle {
-                    mesh: meshes.add(Rectangle::new(a_max_x - a_min_x, a_max_y - a_min_y)).into(),
-                    material: materials.add(Color::RED),
-                    transform: Transform::from_xyz((a_max_x + a_min_x) / 2.0, (a_max_y + a_min_y) / 2.0, 0.0),
-                    ..default()
-                },
-                Debugbox,
-            ));
-            commands.spawn((
-                MaterialMesh2dBundle {
-                    mesh: meshes.add(Rectangle::new(b_max_x - b_min_x, b_max_y - b_min_y)).into(),
-                    material: materials.add(Color::RED),
-                    transform: Transform::from_xyz((b_max_x + b_min_x) / 2.0, (b_max_y + b_min_y) / 2.0, 0.0),
-                    ..default()
-                },
-                Debugbox,
-            ));
-        }
-
-        let overlap_x = a_min_x < b_max_x && a_max_x > b_min_x;
-        let overlap_y = a_min_y < b_max_y && a_max_y > b_min_y;
-        if overlap_x && overlap_y {
-            return true;
-        }
-        false
+        let a = hitbox_a.aabb(transform_a);
+        let b = hitbox_b.aabb(transform_b);
+        let overlap_x = a.min.x < b.max.x && a.max.x > b.min.x;
+        let overlap_y = a.min.y < b.max.y && a.max.y > b.min.y;
+        overlap_x && overlap_y
     }
 }
 
 fn collision_system(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
     mut event_writer: EventWriter<Collision>,
+    mut diagnostics: Diagnostics,
     query: Query<(Entity, &Hitbox, &Transform), Without<InvulnerableAfterSpawn>>
 ) {
+    let start = Instant::now();
     let entities: Vec<(Entity, &Hitbox, &Transform)> = query.iter().collect();
     for (i, (entity_a, hitbox_a, transform_a)) in entities.iter().enumerate() {
         for (entity_b, hitbox_b, transform_b) in entities.iter().skip(i + 1) {
-            if Hitbox::aabb_intersects(
-                hitbox_a, transform_a, hitbox_b, transform_b,
-                if cfg!(feature = "debug") {
-                    Some((&mut commands, &mut meshes, &mut materials))
-                } else {
-                    None
-                }
-            ) {
+            if Hitbox::aabb_intersects(hitbox_a, transform_a, hitbox_b, transform_b) {
                 event_writer.send(Collision { entity_a: *entity_a, entity_b: *entity_b });
             }
         }
     }
+    let pairs = entities.len() * entities.len().saturating_sub(1) / 2;
+    diagnostics.add_measurement(&COLLISION_PAIRS, || pairs as f64);
+    diagnostics.add_measurement(&COLLISION_SYSTEM_TIME, || start.elapsed().as_secs_f64() * 1000.0);
 }
 
 fn invulnerable_after_spawn_system(
@@ -174,7 +96,7 @@ fn invulnerable_after_spawn_system(
             if entity_a == entity_b {
                 continue;
             }
-            if Hitbox::aabb_intersects(hitbox_a, transform_a, hitbox_b, transform_b, None) {
+            if Hitbox::aabb_intersects(hitbox_a, transform_a, hitbox_b, transform_b) {
                 continue 'outer;
             }
         }
@@ -208,31 +130,3 @@ fn collide_system(
 //        }
 //    }
 //}
-
-#[cfg(feature = "debug")]
-fn handle_debugbox_system(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    hitbox_query: Query<(&Hitbox, &Transform)>,
-    debugbox_query: Query<Entity, With<Debugbox>>,
-) {
-    for entity in debugbox_query.iter() {
-        commands.entity(entity).despawn();
-    }
-    for (hitbox, transform) in hitbox_query.iter() {
-        commands.spawn((
-            MaterialMesh2dBundle {
-                mesh: meshes.add(Rectangle::new(hitbox.width, hitbox.height)).into(),
-                material: materials.add(if hitbox.colliding { Color::BLUE } else { Color::GREEN }),
-                transform: Transform {
-                    translation: transform.translation.clone(),
-                    rotation: transform.rotation.clone(),
-                    ..default()
-                },
-                ..default()
-            },
-            Debugbox,
-        ));
-    }
-}
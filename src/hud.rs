@@ -1,29 +1,45 @@
 use bevy::prelude::*;
 use crate::gamestate::GameState;
+use crate::gamestate::GameDespawnable;
+use crate::player::Compartment;
+use crate::player::Compartments;
+use crate::actions::Action;
+use crate::actions::Bindings;
 
 pub struct HudPlugin;
 impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Loading), spawn_controls_sheet);
+        app.add_systems(OnEnter(GameState::Loading), spawn_controls_sheet)
+            .add_systems(OnEnter(GameState::Loading), spawn_compartment_status)
+            .add_systems(Update, compartment_status_system.run_if(in_state(GameState::Game)))
+            .add_systems(Update, controls_sheet_system.run_if(resource_changed::<Bindings>));
     }
 }
 
 #[derive(Component)]
 struct HudComponent;
 
+#[derive(Component)]
+struct CompartmentStatus;
+
+#[derive(Component)]
+struct ControlsSheet;
+
 fn spawn_controls_sheet(
     mut commands: Commands,
+    bindings: Res<Bindings>,
 ) {
     commands.spawn((
         NodeBundle {
             style: Style {
                 width: Val::Px(300.0), // Largura da caixa
-                height: Val::Px(150.0), // Altura da caixa
+                // No fixed height: the box grows with the text as actions are added
                 align_items: AlignItems::Start,
                 justify_content: JustifyContent::Start,
                 padding: UiRect {
                     left: Val::Percent(1.0),
                     top: Val::Percent(1.0),
+                    bottom: Val::Percent(1.0),
                     ..default()
                 },
                 ..default()
@@ -34,13 +50,78 @@ fn spawn_controls_sheet(
         HudComponent,
     )).with_children(|parent| {
         // Cada TextBundle agora é um filho direto do NodeBundle com fundo preto, centralizado dentro da caixa
-        parent.spawn(TextBundle::from_section(
-            "INSTRUCTIONS:\nRotate sub: A/D/<-/->\nFire regular: SPACE\nFire guided: SHIFT\nFire counter: CTRL",
+        parent.spawn((
+            TextBundle::from_section(
+                controls_text(&bindings),
+                TextStyle {
+                    font_size: 20.0, // Ajuste o tamanho da fonte conforme necessário
+                    color: Color::rgb(0.8, 0.0, 0.5),
+                    ..default()
+                },
+            ),
+            ControlsSheet,
+        ));
+    });
+}
+
+fn controls_text(bindings: &Bindings) -> String {
+    let mut text = "INSTRUCTIONS:".to_string();
+    for action in Action::ALL {
+        text.push_str(&format!("\n{}: {}", action.label(), bindings.describe(action)));
+    }
+    text
+}
+
+fn controls_sheet_system(
+    bindings: Res<Bindings>,
+    mut text_query: Query<&mut Text, With<ControlsSheet>>,
+) {
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = controls_text(&bindings);
+    }
+}
+
+fn spawn_compartment_status(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
             TextStyle {
-                font_size: 20.0, // Ajuste o tamanho da fonte conforme necessário
+                font_size: 20.0,
                 color: Color::rgb(0.8, 0.0, 0.5),
                 ..default()
             },
-        ));
-    });
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        CompartmentStatus,
+        GameDespawnable,
+    ));
+}
+
+fn compartment_status_system(
+    compartments_query: Query<&Compartments>,
+    mut text_query: Query<&mut Text, With<CompartmentStatus>>,
+) {
+    let Ok(compartments) = compartments_query.get_single() else { return; };
+    let Ok(mut text) = text_query.get_single_mut() else { return; };
+    let status = |compartment: &Compartment| {
+        if !compartment.operational() {
+            "OFFLINE"
+        } else if compartment.damaged() {
+            "DAMAGED"
+        } else {
+            "OK"
+        }
+    };
+    text.sections[0].value = format!(
+        "SONAR: {}\nTUBES: {}\nTURBINES: {}",
+        status(&compartments.sonar),
+        status(&compartments.tubes),
+        status(&compartments.turbines),
+    );
 }
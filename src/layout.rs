@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use bevy::window::*;
+use bevy::transform::TransformSystem;
+use crate::sonar::Sonar;
+
+pub struct LayoutPlugin;
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, layout_system.before(TransformSystem::TransformPropagate));
+    }
+}
+
+/// Where a sprite sits on screen, recomputed whenever the window or the sprite's image changes
+#[derive(Component, Clone, Copy)]
+pub enum Anchor {
+    /// Stretched over the whole visible area
+    Fill,
+    /// Scaled and moved so the scope ring painted on painel.png frames the sonar
+    SonarPanel,
+    /// Scaled to the sonar's diameter, staying where it was spawned
+    SonarScope,
+    /// Offset from the top-left corner of the visible area, both given at 1080p
+    TopLeft { offset: Vec2, scale: f32 },
+}
+
+/// Heights are laid out against this, so `TopLeft` offsets look the same at every resolution
+pub const REFERENCE_HEIGHT: f32 = 1080.0;
+
+// The scope ring on painel.png, as fractions of the image size (origin at the top-left corner)
+const PANEL_SCOPE_CENTER: Vec2 = Vec2::new(0.512, 0.391);
+const PANEL_SCOPE_RADIUS: f32 = 0.29; // of the image height
+
+#[allow(clippy::too_many_arguments)]
+fn layout_system(
+    mut resized: EventReader<WindowResized>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    added: Query<(), Added<Anchor>>,
+    mut removed_sonars: RemovedComponents<Sonar>,
+    images: Res<Assets<Image>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<&mut OrthographicProjection, With<Camera2d>>,
+    mut anchored: Query<(&Anchor, &Handle<Image>, &mut Transform, Option<&Sonar>)>,
+) {
+    let loaded = image_events.read().filter(|e| matches!(e, AssetEvent::LoadedWithDependencies { .. })).count() > 0;
+    let resized = resized.read().count() > 0;
+    let sonar_removed = removed_sonars.read().count() > 0;
+    if !resized && !loaded && !sonar_removed && added.is_empty() {
+        return;
+    }
+    let Ok(window) = windows.get_single() else { return; };
+    let window_size = Vec2::new(window.resolution.width(), window.resolution.height());
+    if window_size.y <= 0.0 {
+        return;
+    }
+
+    // Zoom so the sonar always fills the window's height, whatever size it was spawned at
+    let sonar = anchored.iter().find_map(|(_, _, transform, sonar)| sonar.map(|s| (s.radius, transform.translation.xy())));
+    let zoom = sonar.map_or(1.0, |(radius, _)| radius * 2.0 / window_size.y);
+    for mut projection in cameras.iter_mut() {
+        if projection.scale != zoom {
+            projection.scale = zoom;
+        }
+    }
+    let visible = window_size * zoom;
+    let unit = visible.y / REFERENCE_HEIGHT;
+
+    for (anchor, image, mut transform, _) in anchored.iter_mut() {
+        let Some(image) = images.get(image) else { continue; };
+        let image_size = image.size_f32();
+        match *anchor {
+            Anchor::Fill => {
+                transform.translation.x = 0.0;
+                transform.translation.y = 0.0;
+                transform.scale = (visible / image_size).extend(1.0);
+            }
+            Anchor::SonarPanel => {
+                let Some((radius, center)) = sonar else { continue; };
+                let scale = radius / (PANEL_SCOPE_RADIUS * image_size.y);
+                let offset = (PANEL_SCOPE_CENTER - Vec2::splat(0.5)) * image_size * Vec2::new(1.0, -1.0) * scale;
+                let position = center - offset;
+                transform.translation.x = position.x;
+                transform.translation.y = position.y;
+                transform.scale = Vec3::new(scale, scale, 1.0);
+            }
+            Anchor::SonarScope => {
+                let Some((radius, _)) = sonar else { continue; };
+                transform.scale = Vec3::splat(radius * 2.0 / image_size.y);
+            }
+            Anchor::TopLeft { offset, scale } => {
+                transform.translation.x = -visible.x / 2.0 + offset.x * unit;
+                transform.translation.y = visible.y / 2.0 - offset.y * unit;
+                transform.scale = Vec3::splat(scale * unit);
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::Duration;
+use bevy::prelude::*;
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::input::InputPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::ExitCondition;
+pub mod gamestate;
+pub mod hud;
+pub mod sonar;
+pub mod torpedo;
+pub mod player;
+pub mod menu;
+pub mod loading;
+pub mod pause;
+pub mod enemy;
+pub mod hitbox;
+pub mod gameover;
+pub mod feedback;
+pub mod sound;
+pub mod settings;
+pub mod actions;
+pub mod widgets;
+pub mod layout;
+pub mod simulation;
+pub mod replay;
+pub mod cli;
+pub mod console;
+pub mod overlay;
+pub mod diagnostics;
+pub mod tracking;
+pub mod firecontrol;
+pub mod scope;
+pub mod biologics;
+pub mod decoy;
+#[cfg(feature = "embedded_assets")]
+mod embedded;
+
+/// The game as it is played, with a window, renderer and audio
+pub fn build_app(settings: settings::Settings) -> App {
+    let mut app = App::new();
+    #[cfg(feature = "embedded_assets")]
+    embedded::register_embedded_assets(&mut app);
+    app
+        .add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(settings.window()),
+            //exit_condition: ExitCondition::OnAllClosed, //it tries to exit app, but bugs out
+            ..default()
+        }))
+        .insert_resource(settings)
+        .insert_resource(actions::Bindings::load())
+        .add_systems(Startup, setup_cam)
+        .add_plugins(overlay::OverlayPlugin);
+    add_game_plugins(&mut app);
+    app
+}
+
+/// The game without window, renderer, audio or asset files, either stepped by hand with `App::update`
+/// or run as fast as the CPU allows.
+/// Every frame advances time by exactly one gameplay tick, the seed is 0, and settings and bindings
+/// are the defaults instead of whatever the user has saved, so tests see the same game every run.
+pub fn build_headless_app() -> App {
+    let mut app = App::new();
+    app
+        .add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)),
+            AssetPlugin::default(),
+            InputPlugin,
+            TransformPlugin,
+            HierarchyPlugin,
+            WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            },
+        ))
+        .init_asset::<Image>()
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .init_asset::<AudioSource>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / simulation::TICK_RATE)))
+        .insert_resource(settings::Settings::default())
+        .insert_resource(actions::Bindings::default())
+        .insert_resource(simulation::GameRng::new(0))
+        .insert_resource(replay::Recording::unsaved())
+        .insert_resource(loading::GameAssets::placeholder());
+    add_game_plugins(&mut app);
+    app
+}
+
+fn add_game_plugins(app: &mut App) {
+    app
+        .init_resource::<gamestate::PlayArea>()
+        .init_state::<gamestate::GameState>()
+        .add_plugins(hud::HudPlugin)
+        .add_plugins(menu::MenuPlugin)
+        .add_plugins(loading::LoadingPlugin)
+        .add_plugins(pause::PausePlugin)
+        .add_plugins(sonar::SonarPlugin)
+        .add_plugins(tracking::TrackingPlugin)
+        .add_plugins(firecontrol::FireControlPlugin)
+        .add_plugins(scope::ScopePlugin)
+        .add_plugins(player::PlayerPlugin)
+        .add_plugins(torpedo::TorpedoPlugin)
+        .add_plugins(enemy::EnemyPlugin)
+        .add_plugins(biologics::BiologicsPlugin)
+        .add_plugins(decoy::DecoyPlugin)
+        .add_plugins(hitbox::HitboxPlugin)
+        .add_plugins(gameover::GameOverPlugin)
+        .add_plugins(feedback::FeedbackPlugin)
+        .add_plugins(sound::SoundPlugin)
+        .add_plugins(settings::SettingsPlugin)
+        .add_plugins(actions::ActionsPlugin)
+        .add_plugins(widgets::WidgetsPlugin)
+        .add_plugins(layout::LayoutPlugin)
+        .add_plugins(simulation::SimulationPlugin)
+        .add_plugins(replay::ReplayPlugin)
+        .add_plugins(console::ConsolePlugin)
+        .add_plugins(diagnostics::DiagnosticsHudPlugin);
+}
+
+fn setup_cam(
+    mut commands: Commands,
+) {
+    commands.spawn((Camera2dBundle::default(), feedback::CameraShake::default()));
+}
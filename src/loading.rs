@@ -1,5 +1,7 @@
 use bevy::prelude::*;
-use iyes_progress::{prelude::*, dummy_system_wait_millis};
+use bevy::asset::LoadState;
+use bevy::asset::UntypedAssetId;
+use iyes_progress::prelude::*;
 use crate::gamestate::GameState;
 use crate::gamestate::despawn_system;
 use crate::gamestate::LoadingDespawnable;
@@ -8,21 +10,118 @@ pub struct LoadingPlugin;
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(ProgressPlugin::new(GameState::Loading).continue_to(GameState::Game))
-            .add_systems(OnEnter(GameState::Loading), spawn_loading_screen)
+            .add_systems(OnEnter(GameState::Loading), (request_game_assets.in_set(RequestAssets), spawn_loading_screen))
             .add_systems(Update, (
-                    dummy_system_wait_millis::<500>.track_progress(),
+                    asset_progress_system.track_progress(),
                     progress_percent_system.after(TrackedProgressSet),
+                    load_error_system,
                 ).run_if(in_state(GameState::Loading))
             )
             .add_systems(OnExit(GameState::Loading), despawn_system::<LoadingDespawnable>);
     }
 }
 
+/// Systems spawning game entities on `OnEnter(GameState::Loading)` must run after this,
+/// so `GameAssets` is available to them
+#[derive(SystemSet, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct RequestAssets;
+
+/// Every file the game needs, requested up front so nothing pops in mid-game
+#[derive(Resource)]
+pub struct GameAssets {
+    pub submarine: Handle<Image>,
+    pub heart: Handle<Image>,
+    pub radar: Handle<Image>,
+    pub panel: Handle<Image>,
+    pub enemy: Handle<Image>,
+    pub regular_torpedo: Handle<Image>,
+    pub guided_torpedo: Handle<Image>,
+    pub counter_torpedo: Handle<Image>,
+    pub ping: Handle<AudioSource>,
+    pub launch: Handle<AudioSource>,
+    pub explosion: Handle<AudioSource>,
+    pub ambience: Handle<AudioSource>,
+}
+
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> GameAssets {
+        let asset_server = world.resource::<AssetServer>();
+        GameAssets {
+            submarine: asset_server.load("submarino.png"),
+            heart: asset_server.load("heart.png"),
+            radar: asset_server.load("radar.png"),
+            panel: asset_server.load("painel.png"),
+            enemy: asset_server.load("enemy.png"),
+            regular_torpedo: asset_server.load("torpedo-comum.png"),
+            guided_torpedo: asset_server.load("torpedo-teleguiado.png"),
+            counter_torpedo: asset_server.load("torpedo-contramedida.png"),
+            ping: asset_server.load("sounds/ping.wav"),
+            launch: asset_server.load("sounds/launch.wav"),
+            explosion: asset_server.load("sounds/explosion.wav"),
+            ambience: asset_server.load("sounds/ambience.wav"),
+        }
+    }
+}
+
+impl GameAssets {
+    /// Handles that point at nothing, for running without an asset loader (see `build_headless_app`)
+    pub fn placeholder() -> GameAssets {
+        GameAssets {
+            submarine: Handle::default(),
+            heart: Handle::default(),
+            radar: Handle::default(),
+            panel: Handle::default(),
+            enemy: Handle::default(),
+            regular_torpedo: Handle::default(),
+            guided_torpedo: Handle::default(),
+            counter_torpedo: Handle::default(),
+            ping: Handle::default(),
+            launch: Handle::default(),
+            explosion: Handle::default(),
+            ambience: Handle::default(),
+        }
+    }
+
+    /// Ids of every handle that was actually requested from a file;
+    /// placeholders are left out so they never hold up the loading screen
+    pub fn ids(&self) -> Vec<UntypedAssetId> {
+        [
+            self.submarine.clone().untyped(),
+            self.heart.clone().untyped(),
+            self.radar.clone().untyped(),
+            self.panel.clone().untyped(),
+            self.enemy.clone().untyped(),
+            self.regular_torpedo.clone().untyped(),
+            self.guided_torpedo.clone().untyped(),
+            self.counter_torpedo.clone().untyped(),
+            self.ping.clone().untyped(),
+            self.launch.clone().untyped(),
+            self.explosion.clone().untyped(),
+            self.ambience.clone().untyped(),
+        ]
+        .into_iter()
+        .filter(|handle| handle.path().is_some())
+        .map(|handle| handle.id())
+        .collect()
+    }
+}
+
 #[derive(Component)]
 struct ProgressPercent;
 
+#[derive(Component)]
+struct LoadErrors;
+
 const BACKGROUND: Color = Color::rgba(0.15, 0.15, 0.15, 0.30);
 
+// Exclusive so the resource exists right away for the spawn systems ordered after it
+fn request_game_assets(world: &mut World) {
+    if !world.contains_resource::<GameAssets>() {
+        let assets = GameAssets::from_world(world);
+        world.insert_resource(assets);
+    }
+}
+
 fn spawn_loading_screen(
     mut commands: Commands,
 ) {
@@ -31,6 +130,7 @@ fn spawn_loading_screen(
             style: Style {
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
                 align_items: AlignItems::Center,
                 justify_content: JustifyContent::Center,
                 ..default()
@@ -51,9 +151,54 @@ fn spawn_loading_screen(
             ),
             ProgressPercent,
         ));
+        parent.spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 30.0,
+                    color: Color::rgb(0.9, 0.2, 0.2),
+                    ..default()
+                },
+            ),
+            LoadErrors,
+        ));
     });
 }
 
+fn asset_progress_system(
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+) -> Progress {
+    let ids = assets.ids();
+    let done = ids.iter().filter(|id| asset_server.get_load_state(**id) == Some(LoadState::Loaded)).count();
+    Progress {
+        done: done as u32,
+        total: ids.len() as u32,
+    }
+}
+
+fn load_error_system(
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    mut text_query: Query<&mut Text, With<LoadErrors>>,
+) {
+    let failed: Vec<String> = assets.ids().into_iter()
+        .filter(|id| asset_server.get_load_state(*id) == Some(LoadState::Failed))
+        .map(|id| asset_server.get_path(id).map_or_else(|| format!("{:?}", id), |path| path.to_string()))
+        .collect();
+    let message = if failed.is_empty() {
+        String::new()
+    } else {
+        format!("Failed to load:\n{}", failed.join("\n"))
+    };
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != message {
+            error!("{}", message);
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
 fn progress_percent_system(
     counter: Res<ProgressCounter>,
     mut text_query: Query<&mut Text, With<ProgressPercent>>,
@@ -64,5 +209,5 @@ fn progress_percent_system(
         percent = 0.0;
     }
     let mut text = text_query.single_mut();
-    text.sections[0].value = format!("Loading {:.0}%", percent);
+    text.sections[0].value = format!("Loading {:.0}%", percent * 100.0);
 }
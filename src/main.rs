@@ -1,45 +1,31 @@
-use bevy::prelude::*;
-use bevy::window::*;
-mod gamestate;
-mod hud;
-mod sonar;
-mod torpedo;
-mod player;
-mod menu;
-mod loading;
-mod pause;
-mod enemy;
-mod hitbox;
-mod gameover;
+use miles_below_darkness::build_app;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::cli::Cli;
+use miles_below_darkness::cli::USAGE;
+use miles_below_darkness::settings::Settings;
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Miles Below Darkness".to_string(),
-                resolution: WindowResolution::new(1920., 1080.).with_scale_factor_override(1.0),
-                ..default()
-            }),
-            //exit_condition: ExitCondition::OnAllClosed, //it tries to exit app, but bugs out
-            ..default()
-        }))
-        .init_state::<gamestate::GameState>()
-        .add_systems(Startup, setup_cam)
-        .add_plugins(hud::HudPlugin)
-        .add_plugins(menu::MenuPlugin)
-        .add_plugins(loading::LoadingPlugin)
-        .add_plugins(pause::PausePlugin)
-        .add_plugins(sonar::SonarPlugin)
-        .add_plugins(player::PlayerPlugin)
-        .add_plugins(torpedo::TorpedoPlugin)
-        .add_plugins(enemy::EnemyPlugin)
-        .add_plugins(hitbox::HitboxPlugin)
-        .add_plugins(gameover::GameOverPlugin)
-        .run()
-}
-
-fn setup_cam(
-    mut commands: Commands,
-) {
-    commands.spawn(Camera2dBundle::default());
+    let cli = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if cli.help {
+        println!("{}", USAGE);
+        return;
+    }
+    let mut app = if cli.headless {
+        build_headless_app()
+    } else {
+        let mut settings = Settings::load();
+        cli.apply_to_settings(&mut settings);
+        build_app(settings)
+    };
+    if let Err(e) = cli.configure(&mut app) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    app.run();
 }
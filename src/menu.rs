@@ -3,57 +3,46 @@ use bevy::app::AppExit;
 use crate::gamestate::GameState;
 use crate::gamestate::despawn_system;
 use crate::gamestate::MenuDespawnable;
-use bevy::window::*;
+use crate::settings::SettingsReturn;
+use crate::widgets::Activated;
+use crate::widgets::button_activation_system;
+use crate::widgets::button_style;
+use crate::widgets::button_text_style;
+use crate::widgets::spawn_button;
+use crate::layout::Anchor;
+use crate::simulation::GameRng;
 
 pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, button_system.run_if(in_state(GameState::Menu)))
-            .add_systems(Update, menu_action.run_if(in_state(GameState::Menu)))
+        app.add_event::<Activated<MenuButtonAction>>()
+            .add_systems(Update, (button_activation_system::<MenuButtonAction>, menu_action).chain().run_if(in_state(GameState::Menu)))
             .add_systems(OnEnter(GameState::Menu), spawn_menu)
             .add_systems(OnExit(GameState::Menu), despawn_system::<MenuDespawnable>);
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 enum MenuButtonAction {
     Play,
+    Replays,
+    Settings,
     Quit,
 }
 
-const NORMAL_BUTTON: Color = Color::rgba(0.15, 0.15, 0.15,0.9);
 const BACKGROUND: Color = Color::rgba(0.15, 0.15, 0.15, 0.30);
-const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
-const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+const TEXT_COLOR: Color = Color::rgba(0.5, 0.0, 0.5, 0.5);
 
 fn spawn_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut windows: Query<&mut Window, With<PrimaryWindow>>,
 ) {
-
-    let window = windows.single_mut();
-    //let radius = window.resolution.height() / 2.0;
-    let background_height = 1024.0; 
-    let background_width = 1792.0;
-
-    let painel_center_x = (window.resolution.width() / 2.0) - 960.0;
-    let painel_center_y = (window.resolution.height() / 2.0) - 560.0;
-    
-    
-    let scale_factor_x = (window.resolution.width() / background_width) * 1.0; 
-    let scale_factor_y = (window.resolution.height() / background_height) * 1.0; 
-
-    
     commands.spawn((SpriteBundle {
-        texture: asset_server.load("../assets/menu-inicial.png"),
-        transform: Transform {
-            translation: Vec3::new(painel_center_x, painel_center_y, -3.0),
-            scale: Vec3::new(scale_factor_x, scale_factor_y, 1.0), 
-            ..default()
-        },
+        texture: asset_server.load("menu-inicial.png"),
+        transform: Transform::from_xyz(0.0, 0.0, -3.0),
         ..default()
     },
+    Anchor::Fill,
     MenuDespawnable,
 ));
 
@@ -72,113 +61,59 @@ fn spawn_menu(
         },
         MenuDespawnable,
     )).with_children(|parent| {
-        parent.spawn((
-            ButtonBundle {
-                style: Style {
-                    width: Val::Px(150.0),
-                    height: Val::Px(65.0),
+        let buttons = [
+            ("Play", MenuButtonAction::Play, 150.0, 50.0),
+            ("Replays", MenuButtonAction::Replays, 200.0, 0.0),
+            ("Settings", MenuButtonAction::Settings, 200.0, 0.0),
+            ("Quit", MenuButtonAction::Quit, 150.0, 0.0),
+        ];
+        for (label, action, width, left) in buttons {
+            spawn_button(
+                parent,
+                label,
+                action,
+                Style {
                     margin: UiRect {
                         top: Val::Px(-320.0),
-                        left: Val::Px(50.0), // Adiciona margem à esquerda
+                        left: Val::Px(left), // Adiciona margem à esquerda
                         ..default()
                     },
-                    border: UiRect::all(Val::Px(5.0)),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
+                    ..button_style(Val::Px(width), Val::Px(65.0))
                 },
-                
-                ..default()
-            },
-            MenuButtonAction::Play
-        )).with_children(|parent| {
-            parent.spawn(
-                TextBundle::from_section(
-                    "Play",
-                    TextStyle {
-                        //font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                        font_size: 40.0,
-                        color: Color::rgba(0.5, 0.0, 0.5, 0.5),
-                        ..default()
-                    },
-                )
-            );
-        });
-        parent.spawn((
-            ButtonBundle {
-                style: Style {
-                    width: Val::Px(150.0),
-                    height: Val::Px(65.0),
-                    margin: UiRect {
-                        top: Val::Px(-320.0),
-                        left: Val::Px(0.0), // Adiciona margem à esquerda
-                        ..default()
-                    },
-                    border: UiRect::all(Val::Px(5.0)),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
+                TextStyle {
+                    //font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    color: TEXT_COLOR,
+                    ..button_text_style(40.0)
                 },
-                background_color: NORMAL_BUTTON.into(),
-                ..default()
-            },
-            MenuButtonAction::Quit
-        )).with_children(|parent| {
-            parent.spawn(
-                TextBundle::from_section(
-                    "Quit",
-                    TextStyle {
-                        //font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                        font_size: 40.0,
-                        color: Color::rgba(0.5, 0.0, 0.5, 0.5),
-                        ..default()
-                    },
-                )
             );
-        });
+        }
     });
 }
 
 fn menu_action(
-    interaction_query: Query<(&Interaction, &MenuButtonAction), (Changed<Interaction>, With<Button>)>,
+    mut activated: EventReader<Activated<MenuButtonAction>>,
     mut app_exit_events: EventWriter<AppExit>,
     mut game_state: ResMut<NextState<GameState>>,
     mut time: ResMut<Time<Virtual>>,
+    mut settings_return: ResMut<SettingsReturn>,
+    mut rng: ResMut<GameRng>,
 ) {
-    for (interaction, action) in &interaction_query {
-        if *interaction == Interaction::Pressed {
-            match action {
-                MenuButtonAction::Quit => {
-                    app_exit_events.send(AppExit);
-                }
-                MenuButtonAction::Play => {
-                    game_state.set(GameState::Loading);
-                    time.unpause();
-                }
+    for Activated(action) in activated.read() {
+        match action {
+            MenuButtonAction::Quit => {
+                app_exit_events.send(AppExit);
             }
-        }
-    }
-}
-
-fn button_system(
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &mut BorderColor),
-        (Changed<Interaction>, With<Button>),
-    >,
-) {
-    for (interaction, mut color, mut border_color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Pressed => {
-                *color = PRESSED_BUTTON.into();
-                border_color.0 = Color::RED;
+            MenuButtonAction::Replays => {
+                game_state.set(GameState::Replays);
             }
-            Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
-                border_color.0 = Color::WHITE;
+            MenuButtonAction::Settings => {
+                settings_return.0 = GameState::Menu;
+                game_state.set(GameState::Settings);
             }
-            Interaction::None => {
-                *color = NORMAL_BUTTON.into();
-                border_color.0 = Color::BLACK;
+            MenuButtonAction::Play => {
+                rng.reroll();
+                game_state.set(GameState::Loading);
+                time.unpause();
             }
         }
     }
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use crate::gamestate::GameState;
+use crate::hitbox::Hitbox;
+use crate::enemy::Enemy;
+use crate::enemy::EnemyPositions;
+use crate::sonar::Sonar;
+use crate::sonar::Line;
+use crate::torpedo::GuidedTorpedo;
+use crate::torpedo::CounterTorpedo;
+use crate::torpedo::PlayerTorpedo;
+use crate::torpedo::GUIDED_RANGE;
+use crate::torpedo::COUNTER_RANGE;
+use crate::torpedo::guided_aim;
+use crate::torpedo::Lure;
+use crate::decoy::EnemyDecoy;
+use crate::torpedo::TrackTarget;
+use crate::tracking::Tracks;
+use crate::simulation::SimulationTick;
+use crate::console::ConsoleCommandsExt;
+use crate::console::ConsoleResult;
+
+/// Debug drawing on top of the game, needs the gizmos from `DefaultPlugins` so only the windowed app has it
+pub struct OverlayPlugin;
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Overlay>()
+            .add_systems(Update, overlay_keys_system)
+            .add_systems(Update, hitbox_overlay_system.run_if(in_state(GameState::Game)).run_if(|overlay: Res<Overlay>| overlay.hitboxes))
+            .add_systems(Update, destination_overlay_system.run_if(in_state(GameState::Game)).run_if(|overlay: Res<Overlay>| overlay.destinations))
+            .add_systems(Update, target_overlay_system.run_if(in_state(GameState::Game)).run_if(|overlay: Res<Overlay>| overlay.targets))
+            .add_systems(Update, intercept_overlay_system.run_if(in_state(GameState::Game)).run_if(|overlay: Res<Overlay>| overlay.intercepts))
+            .add_systems(Update, sweep_overlay_system.run_if(in_state(GameState::Game)).run_if(|overlay: Res<Overlay>| overlay.sweep))
+            .add_console_command("overlay", "[name]", "Toggle an overlay: hitboxes, destinations, targets, intercepts, sweep, or all of them", overlay_command);
+    }
+}
+
+/// Which overlays are drawn, F2 to F6 toggle them in the order of the fields
+#[derive(Resource)]
+pub struct Overlay {
+    pub hitboxes: bool,
+    pub destinations: bool,
+    pub targets: bool,
+    pub intercepts: bool,
+    pub sweep: bool,
+}
+
+// Debug builds start with hitboxes shown, which clippy can't see from a release build
+#[allow(clippy::derivable_impls)]
+impl Default for Overlay {
+    fn default() -> Overlay {
+        Overlay {
+            hitboxes: cfg!(feature = "debug"),
+            destinations: false,
+            targets: false,
+            intercepts: false,
+            sweep: false,
+        }
+    }
+}
+
+impl Overlay {
+    fn get_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "hitboxes" => Some(&mut self.hitboxes),
+            "destinations" => Some(&mut self.destinations),
+            "targets" => Some(&mut self.targets),
+            "intercepts" => Some(&mut self.intercepts),
+            "sweep" => Some(&mut self.sweep),
+            _ => None,
+        }
+    }
+
+    fn all_mut(&mut self) -> [&mut bool; 5] {
+        [&mut self.hitboxes, &mut self.destinations, &mut self.targets, &mut self.intercepts, &mut self.sweep]
+    }
+}
+
+fn overlay_keys_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<Overlay>,
+) {
+    let keys = [KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6];
+    for (key, enabled) in keys.into_iter().zip(overlay.all_mut()) {
+        if keyboard_input.just_pressed(key) {
+            *enabled = !*enabled;
+        }
+    }
+}
+
+fn overlay_command(
+    In(args): In<Vec<String>>,
+    mut overlay: ResMut<Overlay>,
+) -> ConsoleResult {
+    let name = args.first().map_or("all", |name| name.as_str());
+    if name == "all" {
+        let enable = overlay.all_mut().iter().any(|enabled| !**enabled);
+        for enabled in overlay.all_mut() {
+            *enabled = enable;
+        }
+        return Ok(format!("all overlays {}", if enable { "on" } else { "off" }));
+    }
+    let enabled = overlay.get_mut(name).ok_or_else(|| format!("unknown overlay '{}'", name))?;
+    *enabled = !*enabled;
+    Ok(format!("{} {}", name, if *enabled { "on" } else { "off" }))
+}
+
+// The rotated box in green (blue once it has collided) and, in red, the axis-aligned box collisions are tested with
+fn hitbox_overlay_system(
+    mut gizmos: Gizmos,
+    query: Query<(&Hitbox, &Transform)>,
+) {
+    for (hitbox, transform) in query.iter() {
+        let (angle, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+        let color = if hitbox.colliding { Color::BLUE } else { Color::GREEN };
+        gizmos.rect_2d(transform.translation.xy(), angle, Vec2::new(hitbox.width, hitbox.height), color);
+        let aabb = hitbox.aabb(transform);
+        gizmos.rect_2d(aabb.center(), 0.0, aabb.size(), Color::RED);
+    }
+}
+
+fn destination_overlay_system(
+    mut gizmos: Gizmos,
+    query: Query<(&Enemy, &Transform)>,
+) {
+    for (enemy, transform) in query.iter() {
+        let destination = enemy.destination().xy();
+        gizmos.line_2d(transform.translation.xy(), destination, Color::ORANGE);
+        gizmos.circle_2d(destination, 4.0, Color::ORANGE);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn target_overlay_system(
+    mut gizmos: Gizmos,
+    enemy_positions: Res<EnemyPositions>,
+    tracks: Res<Tracks>,
+    tick: Res<SimulationTick>,
+    query: Query<(&Transform, Option<&TrackTarget>), (With<GuidedTorpedo>, With<PlayerTorpedo>)>,
+    decoy_query: Query<&Transform, With<EnemyDecoy>>,
+) {
+    let lures = Lure::gather(enemy_positions.positions.iter().copied(), decoy_query.iter().map(|transform| transform.translation));
+    for (transform, track_target) in query.iter() {
+        let position = transform.translation.xy();
+        gizmos.circle_2d(position, GUIDED_RANGE, Color::rgba(1.0, 0.0, 1.0, 0.3));
+        if let Some(target) = guided_aim(track_target, &tracks, tick.0, &lures, transform.translation) {
+            gizmos.line_2d(position, target.xy(), Color::FUCHSIA);
+        }
+    }
+}
+
+fn intercept_overlay_system(
+    mut gizmos: Gizmos,
+    query: Query<&Transform, With<CounterTorpedo>>,
+) {
+    for transform in query.iter() {
+        gizmos.circle_2d(transform.translation.xy(), COUNTER_RANGE, Color::YELLOW);
+    }
+}
+
+fn sweep_overlay_system(
+    mut gizmos: Gizmos,
+    sonar_query: Query<(&Sonar, &Transform)>,
+    line_query: Query<&Transform, With<Line>>,
+) {
+    for (sonar, sonar_transform) in sonar_query.iter() {
+        let center = sonar_transform.translation.xy();
+        gizmos.circle_2d(center, sonar.range(), Color::CYAN);
+        for line_transform in line_query.iter() {
+            let tip = center + (line_transform.translation.xy() - center) * 2.0;
+            gizmos.line_2d(center, tip, Color::CYAN);
+        }
+    }
+}
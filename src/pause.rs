@@ -3,30 +3,36 @@ use bevy::app::AppExit;
 use crate::gamestate::GameState;
 use crate::gamestate::despawn_system;
 use crate::gamestate::PauseDespawnable;
+use crate::settings::SettingsReturn;
+use crate::actions::Action;
+use crate::actions::ActionState;
+use crate::widgets::Activated;
+use crate::widgets::button_activation_system;
+use crate::widgets::button_style;
+use crate::widgets::button_text_style;
+use crate::widgets::spawn_button;
 
 pub struct PausePlugin;
 impl Plugin for PausePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, pause_system.run_if(in_state(GameState::Game)))
+        app.add_event::<Activated<PauseButtonAction>>()
+            .add_systems(Update, pause_system.run_if(in_state(GameState::Game)))
             .add_systems(Update, unpause_system.run_if(in_state(GameState::Pause)))
-            .add_systems(Update, pause_menu_action.run_if(in_state(GameState::Pause)))
-            .add_systems(Update, button_system.run_if(in_state(GameState::Pause)))
+            .add_systems(Update, (button_activation_system::<PauseButtonAction>, pause_menu_action).chain().run_if(in_state(GameState::Pause)))
             .add_systems(OnEnter(GameState::Pause), spawn_pause_menu)
             .add_systems(OnExit(GameState::Pause), despawn_system::<PauseDespawnable>);
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 enum PauseButtonAction {
     Unpause,
+    Settings,
     Menu,
     Quit,
 }
 
 const BACKGROUND: Color = Color::rgba(0.15, 0.15, 0.15, 0.30);
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
-const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 
 fn spawn_pause_menu(
     mut commands: Commands,
@@ -45,133 +51,40 @@ fn spawn_pause_menu(
         },
         PauseDespawnable,
     )).with_children(|parent| {
-        parent.spawn((
-            ButtonBundle {
-                style: Style {
-                    width: Val::Px(150.0),
-                    height: Val::Px(65.0),
-                    border: UiRect::all(Val::Px(5.0)),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                border_color: BorderColor(Color::BLACK),
-                background_color: NORMAL_BUTTON.into(),
-                ..default()
-            },
-            PauseButtonAction::Unpause,
-        )).with_children(|parent| {
-            parent.spawn(
-                TextBundle::from_section(
-                    "Back",
-                    TextStyle {
-                        font_size: 40.0,
-                        color: Color::rgb(0.9, 0.9, 0.9),
-                        ..default()
-                    },
-                )
-            );
-        });
-        parent.spawn((
-            ButtonBundle {
-                style: Style {
-                    width: Val::Px(150.0),
-                    height: Val::Px(65.0),
-                    border: UiRect::all(Val::Px(5.0)),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                border_color: BorderColor(Color::BLACK),
-                background_color: NORMAL_BUTTON.into(),
-                ..default()
-            },
-            PauseButtonAction::Menu,
-        )).with_children(|parent| {
-            parent.spawn(
-                TextBundle::from_section(
-                    "Menu",
-                    TextStyle {
-                        font_size: 40.0,
-                        color: Color::rgb(0.9, 0.9, 0.9),
-                        ..default()
-                    },
-                )
-            );
-        });
-        parent.spawn((
-            ButtonBundle {
-                style: Style {
-                    width: Val::Px(150.0),
-                    height: Val::Px(65.0),
-                    border: UiRect::all(Val::Px(5.0)),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                border_color: BorderColor(Color::BLACK),
-                background_color: NORMAL_BUTTON.into(),
-                ..default()
-            },
-            PauseButtonAction::Quit,
-        )).with_children(|parent| {
-            parent.spawn(
-                TextBundle::from_section(
-                    "Quit",
-                    TextStyle {
-                        font_size: 40.0,
-                        color: Color::rgb(0.9, 0.9, 0.9),
-                        ..default()
-                    },
-                )
-            );
-        });
-    });
-}
-
-fn button_system(
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &mut BorderColor),
-        (Changed<Interaction>, With<Button>),
-    >,
-) {
-    for (interaction, mut color, mut border_color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Pressed => {
-                *color = PRESSED_BUTTON.into();
-                border_color.0 = Color::RED;
-            }
-            Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
-                border_color.0 = Color::WHITE;
-            }
-            Interaction::None => {
-                *color = NORMAL_BUTTON.into();
-                border_color.0 = Color::BLACK;
-            }
+        let buttons = [
+            ("Back", PauseButtonAction::Unpause, 150.0),
+            ("Settings", PauseButtonAction::Settings, 200.0),
+            ("Menu", PauseButtonAction::Menu, 150.0),
+            ("Quit", PauseButtonAction::Quit, 150.0),
+        ];
+        for (label, action, width) in buttons {
+            spawn_button(parent, label, action, button_style(Val::Px(width), Val::Px(65.0)), button_text_style(40.0));
         }
-    }
+    });
 }
 
 fn pause_menu_action(
-    interaction_query: Query<(&Interaction, &PauseButtonAction), (Changed<Interaction>, With<Button>)>,
+    mut activated: EventReader<Activated<PauseButtonAction>>,
     mut app_exit_events: EventWriter<AppExit>,
     mut time: ResMut<Time<Virtual>>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut settings_return: ResMut<SettingsReturn>,
 ) {
-    for (interaction, action) in &interaction_query {
-        if *interaction == Interaction::Pressed {
-            match action {
-                PauseButtonAction::Unpause => {
-                    game_state.set(GameState::Game);
-                    time.unpause();
-                }
-                PauseButtonAction::Quit => {
-                    app_exit_events.send(AppExit);
-                }
-                PauseButtonAction::Menu => {
-                    game_state.set(GameState::Menu);
-                }
+    for Activated(action) in activated.read() {
+        match action {
+            PauseButtonAction::Unpause => {
+                game_state.set(GameState::Game);
+                time.unpause();
+            }
+            PauseButtonAction::Settings => {
+                settings_return.0 = GameState::Pause;
+                game_state.set(GameState::Settings);
+            }
+            PauseButtonAction::Quit => {
+                app_exit_events.send(AppExit);
+            }
+            PauseButtonAction::Menu => {
+                game_state.set(GameState::Menu);
             }
         }
     }
@@ -179,10 +92,10 @@ fn pause_menu_action(
 
 fn pause_system(
     mut time: ResMut<Time<Virtual>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    action_state: Res<ActionState>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if action_state.just_pressed(Action::Pause) {
         game_state.set(GameState::Pause);
         time.pause();
     }
@@ -190,10 +103,10 @@ fn pause_system(
 
 fn unpause_system(
     mut time: ResMut<Time<Virtual>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    action_state: Res<ActionState>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if action_state.just_pressed(Action::Pause) {
         game_state.set(GameState::Game);
         time.unpause();
     }
@@ -1,23 +1,41 @@
 use bevy::prelude::*;
-use bevy::window::*;
 use crate::gamestate::GameState;
 use crate::gamestate::despawn_system;
 use crate::gamestate::GameDespawnable;
+use crate::gamestate::PlayArea;
 use crate::hitbox::Hitbox;
 use crate::torpedo::PlayerDamageEvent;
+use crate::actions::ActionState;
+use crate::layout::Anchor;
+use crate::loading::GameAssets;
+use crate::simulation::GameRng;
+use crate::simulation::SimulationSet;
+use crate::torpedo::TorpedoCollisions;
+use crate::loading::RequestAssets;
+use crate::console::ConsoleCommandsExt;
+use crate::console::ConsoleResult;
+use crate::console::console_arg;
+use crate::decoy::DecoyLauncher;
+use rand::Rng;
+use std::time::Duration;
 
 pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Loading), spawn_player)
-            .add_systems(Update, player_rotation_system.run_if(in_state(GameState::Game)))
-            .add_systems(Update, player_damage_system.run_if(in_state(GameState::Game))) // Adicione esta linha
-            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>);
+        app.add_event::<PlayerHitEvent>()
+            .add_systems(OnEnter(GameState::Loading), spawn_player.after(RequestAssets))
+            .add_systems(FixedUpdate, player_rotation_system.in_set(SimulationSet::Movement).run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, player_damage_system.in_set(SimulationSet::Damage).after(TorpedoCollisions).run_if(in_state(GameState::Game))) // Adicione esta linha
+            .add_systems(FixedUpdate, compartment_repair_system.in_set(SimulationSet::Damage).after(player_damage_system).run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, invulnerability_system.in_set(SimulationSet::Damage).after(player_damage_system).run_if(in_state(GameState::Game)))
+            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>)
+            .add_console_command("life", "<n>", "Set the hull to n", life_command)
+            .add_console_command("god", "", "Toggle ignoring all damage", god_command);
     }
 }
 
 #[derive(Component)]
-pub struct PlayerHeart;
+pub struct PlayerHeart(i32);
 
 #[derive(Component)]
 pub struct Player {
@@ -25,22 +43,126 @@ pub struct Player {
     rotation_acceleration: f32,
     terminal_rotation_speed: f32,
     turbine_power: f32,
-    life: i32,
+    max_turbine_power: f32,
+    hull: i32,
+}
+
+impl Player {
+    pub fn hull(&self) -> i32 {
+        self.hull
+    }
+}
+
+/// Set from the console, the player takes no damage at all
+#[derive(Component)]
+pub struct GodMode;
+
+#[derive(Component)]
+pub struct Invulnerable {
+    timer: Timer,
+}
+
+impl Default for Invulnerable {
+    fn default() -> Invulnerable {
+        Invulnerable {
+            timer: Timer::new(Duration::from_millis(1500), TimerMode::Once),
+        }
+    }
+}
+
+/// Sent once for every hit the player actually takes (i.e. not while invulnerable)
+#[derive(Event)]
+pub struct PlayerHitEvent {
+    pub damage: i32,
+    pub direction: Vec2,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum Subsystem {
+    Sonar,
+    Tubes,
+    Turbines,
+}
+
+pub struct Compartment {
+    pub health: i32,
+    pub max_health: i32,
+    repair: Timer,
+}
+
+impl Compartment {
+    pub fn new(max_health: i32) -> Compartment {
+        Compartment {
+            health: max_health,
+            max_health,
+            repair: Timer::new(Duration::from_secs(6), TimerMode::Repeating),
+        }
+    }
+
+    pub fn operational(&self) -> bool {
+        self.health > 0
+    }
+
+    pub fn damaged(&self) -> bool {
+        self.health < self.max_health
+    }
+
+    pub fn efficiency(&self) -> f32 {
+        self.health.max(0) as f32 / self.max_health as f32
+    }
+}
+
+#[derive(Component)]
+pub struct Compartments {
+    pub sonar: Compartment,
+    pub tubes: Compartment,
+    pub turbines: Compartment,
+}
+
+impl Compartments {
+    pub fn get(&self, subsystem: Subsystem) -> &Compartment {
+        match subsystem {
+            Subsystem::Sonar => &self.sonar,
+            Subsystem::Tubes => &self.tubes,
+            Subsystem::Turbines => &self.turbines,
+        }
+    }
+
+    pub fn get_mut(&mut self, subsystem: Subsystem) -> &mut Compartment {
+        match subsystem {
+            Subsystem::Sonar => &mut self.sonar,
+            Subsystem::Tubes => &mut self.tubes,
+            Subsystem::Turbines => &mut self.turbines,
+        }
+    }
+
+    pub fn operational(&self, subsystem: Subsystem) -> bool {
+        self.get(subsystem).operational()
+    }
+}
+
+impl Default for Compartments {
+    fn default() -> Compartments {
+        Compartments {
+            sonar: Compartment::new(2),
+            tubes: Compartment::new(2),
+            turbines: Compartment::new(3),
+        }
+    }
 }
 
 pub fn spawn_player(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    assets: Res<GameAssets>,
+    play_area: Res<PlayArea>,
 ) {
-    let window = windows.single_mut();
-    let radius = window.resolution.height() / 2.0;
+    let radius = play_area.height / 2.0;
     let diameter = radius * 2.0; 
     let scale = diameter / 1024.0; 
     let lifes = if cfg!(feature = "debug") { 15 } else { 5 };
     commands.spawn((
         SpriteBundle {
-            texture: asset_server.load("../assets/submarino.png"),
+            texture: assets.submarine.clone(),
             transform: Transform::from_xyz(0.0, 0.0, 0.0).with_scale(Vec3::splat(0.1*scale)),
             ..default()
         },
@@ -49,9 +171,12 @@ pub fn spawn_player(
             rotation_acceleration: 0.7,
             terminal_rotation_speed: 0.7,
             turbine_power: 1.5,
-            life: lifes,
+            max_turbine_power: 1.5,
+            hull: lifes,
         },
+        Compartments::default(),
         Hitbox::new(30.0, 95.0),
+        DecoyLauncher::default(),
         GameDespawnable,
     ));
 
@@ -59,25 +184,71 @@ pub fn spawn_player(
     //let min_distance = 1.0; // Distância mínima desejada entre os corações
     //let total_distance = heart_width_scaled + min_distance;
 
-    // Spawn heart sprites
-    let heart_texture = asset_server.load("../assets/heart.png");
-    for i in 0..lifes { // Assuming 3 lives
+    spawn_hearts(&mut commands, &assets, 0..lifes);
+}
+
+fn spawn_hearts(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    hearts: std::ops::Range<i32>,
+) {
+    let heart_texture = assets.heart.clone();
+    for i in hearts {
         commands.spawn((
             SpriteBundle {
                 texture: heart_texture.clone(),
-                transform: Transform::from_xyz(-600.0 + i as f32 * 50.0, 420.0, 0.0)
-                .with_scale(Vec3::splat(2.0)), // Adiciona escala ao coração // Adjust position as needed
                 ..default()
             },
-            PlayerHeart,
+            Anchor::TopLeft {
+                offset: Vec2::new(360.0 + i as f32 * 50.0, 120.0),
+                scale: 2.0, // Adiciona escala ao coração
+            },
+            PlayerHeart(i),
             GameDespawnable,
         ));
     }
 }
 
+fn life_command(
+    In(args): In<Vec<String>>,
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut player_query: Query<&mut Player>,
+    heart_query: Query<(Entity, &PlayerHeart)>,
+) -> ConsoleResult {
+    let hull: i32 = console_arg(&args, 0, "n")?;
+    if hull < 1 {
+        return Err("hull must be at least 1".to_string());
+    }
+    let mut player = player_query.get_single_mut().map_err(|_| "there is no player".to_string())?;
+    for (heart_entity, heart) in heart_query.iter() {
+        if heart.0 >= hull {
+            commands.entity(heart_entity).despawn();
+        }
+    }
+    spawn_hearts(&mut commands, &assets, player.hull.min(hull)..hull);
+    player.hull = hull;
+    Ok(format!("hull set to {}", hull))
+}
+
+fn god_command(
+    In(_): In<Vec<String>>,
+    mut commands: Commands,
+    player_query: Query<(Entity, Has<GodMode>), With<Player>>,
+) -> ConsoleResult {
+    let (entity, god) = player_query.get_single().map_err(|_| "there is no player".to_string())?;
+    if god {
+        commands.entity(entity).remove::<GodMode>();
+        Ok("god mode off".to_string())
+    } else {
+        commands.entity(entity).insert(GodMode);
+        Ok("god mode on".to_string())
+    }
+}
+
 pub fn player_rotation_system(
     time: Res<Time>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    action_state: Res<ActionState>,
     mut query: Query<(&mut Player, &mut Transform)>,
     //mut camera: Query<(&Camera, &mut Transform), Without<Player>>,
 ) {
@@ -88,10 +259,9 @@ pub fn player_rotation_system(
         rotation_factor = -player.rotation_speed.signum();
     }
 
-    if keyboard_input.any_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
-        rotation_factor += player.turbine_power;
-    } else if keyboard_input.any_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
-        rotation_factor -= player.turbine_power;
+    let steering = action_state.rotation();
+    if steering != 0.0 {
+        rotation_factor += player.turbine_power * steering;
     } else if player.rotation_speed.abs() < 0.1 {
         player.rotation_speed = 0.0;
     }
@@ -103,21 +273,47 @@ pub fn player_rotation_system(
     player.rotation_speed += rotation_factor * player.rotation_acceleration * time.delta_seconds();
 }
 
+#[allow(clippy::type_complexity)]
 fn player_damage_system(
     mut commands: Commands,
-    mut player_query: Query<(&mut Player, Entity)>,
-    heart_query: Query<Entity, With<PlayerHeart>>,
+    mut player_query: Query<(&mut Player, &mut Compartments, &Transform, Entity, Has<Invulnerable>, Has<GodMode>)>,
+    heart_query: Query<(Entity, &PlayerHeart)>,
     mut damage_events: EventReader<PlayerDamageEvent>,
+    mut hit_events: EventWriter<PlayerHitEvent>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut rng: ResMut<GameRng>,
 ) {
+    let mut hit_this_frame = false;
     for damage_event in damage_events.read() {
-        if let Ok((mut player, player_entity)) = player_query.get_mut(damage_event.entity) {
-            player.life -= damage_event.damage;
-             // Despawn a heart sprite
-             if let Some(heart_entity) = heart_query.iter().next() {
-                commands.entity(heart_entity).despawn();
+        if let Ok((mut player, mut compartments, transform, player_entity, invulnerable, god)) = player_query.get_mut(damage_event.entity) {
+            // The same torpedo keeps colliding until its despawn is applied, so only the first hit counts
+            if invulnerable || god || hit_this_frame {
+                continue;
+            }
+            hit_this_frame = true;
+            commands.entity(player_entity).insert(Invulnerable::default());
+            hit_events.send(PlayerHitEvent {
+                damage: damage_event.damage,
+                direction: (damage_event.source - transform.translation).xy().normalize_or_zero(),
+            });
+            let old_hull = player.hull;
+            player.hull -= damage_event.damage;
+            // Despawn one heart sprite per point of hull lost, rightmost first
+            for (heart_entity, heart) in heart_query.iter() {
+                if heart.0 >= player.hull && heart.0 < old_hull {
+                    commands.entity(heart_entity).despawn();
+                }
             }
-            if player.life <= 0 {
+            let subsystem = match rng.gen_range(0..3) {
+                0 => Subsystem::Sonar,
+                1 => Subsystem::Tubes,
+                _ => Subsystem::Turbines,
+            };
+            let compartment = compartments.get_mut(subsystem);
+            compartment.health = (compartment.health - damage_event.damage).max(0);
+            compartment.repair.reset();
+            player.turbine_power = player.max_turbine_power * compartments.turbines.efficiency();
+            if player.hull <= 0 {
                 // Opcional: Adicione lógica para quando o jogador morre, como despawn da entidade
                 game_state.set(GameState::GameOver); 
                 commands.entity(player_entity).despawn();
@@ -125,3 +321,39 @@ fn player_damage_system(
         }
     }
 }
+
+fn invulnerability_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Sprite)>,
+) {
+    for (entity, mut invulnerable, mut sprite) in query.iter_mut() {
+        invulnerable.timer.tick(time.delta());
+        if invulnerable.timer.finished() {
+            sprite.color.set_a(1.0);
+            commands.entity(entity).remove::<Invulnerable>();
+            continue;
+        }
+        let flash_on = ((invulnerable.timer.elapsed_secs() * 10.0) as u32).is_multiple_of(2);
+        sprite.color.set_a(if flash_on { 0.25 } else { 1.0 });
+    }
+}
+
+pub fn compartment_repair_system(
+    time: Res<Time>,
+    mut player_query: Query<(&mut Player, &mut Compartments)>,
+) {
+    for (mut player, mut compartments) in player_query.iter_mut() {
+        for subsystem in [Subsystem::Sonar, Subsystem::Tubes, Subsystem::Turbines] {
+            let compartment = compartments.get_mut(subsystem);
+            if !compartment.damaged() {
+                continue;
+            }
+            compartment.repair.tick(time.delta());
+            if compartment.repair.just_finished() {
+                compartment.health += 1;
+            }
+        }
+        player.turbine_power = player.max_turbine_power * compartments.turbines.efficiency();
+    }
+}
@@ -0,0 +1,743 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use bevy::prelude::*;
+use bevy::app::FixedMain;
+use bevy::ui::RelativeCursorPosition;
+use crate::gamestate::GameState;
+use crate::gamestate::despawn_system;
+use crate::gamestate::GameDespawnable;
+use crate::gamestate::ReplaysDespawnable;
+use crate::actions::Action;
+use crate::actions::ActionState;
+use crate::settings::config_dir;
+use crate::settings::Difficulty;
+use crate::settings::Settings;
+use crate::enemy::Wave;
+use crate::simulation::GameRng;
+use crate::simulation::SimulationSet;
+use crate::simulation::SimulationTick;
+use crate::simulation::TICK_RATE;
+use crate::loading::RequestAssets;
+use crate::widgets::Activated;
+use crate::widgets::button_activation_system;
+use crate::widgets::button_style;
+use crate::widgets::button_text_style;
+use crate::widgets::spawn_button;
+use crate::widgets::TEXT_COLOR;
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recording>()
+            .init_resource::<Playback>()
+            .add_event::<StartReplay>()
+            .add_event::<Activated<ReplayControl>>()
+            .add_event::<Activated<ReplaysButtonAction>>()
+            .add_systems(Update, start_replay_system)
+            .add_systems(OnEnter(GameState::Loading), (start_recording, spawn_replay_bar).after(RequestAssets))
+            .add_systems(FixedUpdate, (record_input_system, replay_input_system).in_set(SimulationSet::Input).run_if(in_state(GameState::Game)))
+            .add_systems(OnEnter(GameState::GameOver), save_recording)
+            .add_systems(OnEnter(GameState::Menu), (save_recording, stop_playback))
+            .add_systems(Update, (
+                    button_activation_system::<ReplayControl>,
+                    replay_control_system,
+                    scrub_bar_system,
+                    seek_system,
+                    replay_bar_system,
+                ).chain().run_if(playing).run_if(in_state(GameState::Game).or_else(in_state(GameState::GameOver)))
+            )
+            .add_systems(OnEnter(GameState::Replays), spawn_replays_menu)
+            .add_systems(Update, (button_activation_system::<ReplaysButtonAction>, replays_menu_action).chain().run_if(in_state(GameState::Replays)))
+            .add_systems(OnExit(GameState::Replays), despawn_system::<ReplaysDespawnable>);
+    }
+}
+
+/// Actions stored per tick, rotation is stored separately since it is analog
+const RECORDED_ACTIONS: [Action; 7] = [
+    Action::FireRegular,
+    Action::FireGuided,
+    Action::FireCounter,
+    Action::CycleTrack,
+    Action::DesignateTrack,
+    Action::SonarMode,
+    Action::DeployDecoy,
+];
+// One bit each in `TickInput::buttons`
+const _: () = assert!(RECORDED_ACTIONS.len() <= 8);
+const MAGIC: &[u8; 4] = b"MBDR";
+/// Bumped whenever the simulation changes, since the same input would no longer play the same game
+const VERSION: u8 = 6;
+const SPEEDS: [f64; 4] = [1.0, 2.0, 4.0, 8.0];
+/// How far the step keys jump
+const SEEK_STEP: u32 = 5 * TICK_RATE as u32;
+/// Cap on ticks simulated per frame while seeking, so the window stays responsive
+const SEEK_TICKS_PER_FRAME: u32 = 600;
+const MAX_LISTED: usize = 8;
+/// Longest replay that will be loaded, so a small crafted file can't expand into gigabytes of input
+const MAX_TICKS: usize = 6 * 60 * 60 * TICK_RATE as usize;
+
+/// What the player did during one tick
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct TickInput {
+    pub rotation: f32,
+    /// One bit per entry of `RECORDED_ACTIONS`
+    pub buttons: u8,
+}
+
+impl TickInput {
+    fn read(action_state: &ActionState) -> TickInput {
+        let mut buttons = 0;
+        for (i, action) in RECORDED_ACTIONS.iter().enumerate() {
+            if action_state.pressed(*action) {
+                buttons |= 1 << i;
+            }
+        }
+        TickInput {
+            rotation: action_state.rotation(),
+            buttons,
+        }
+    }
+
+    fn apply(&self, action_state: &mut ActionState) {
+        for (i, action) in RECORDED_ACTIONS.iter().enumerate() {
+            action_state.set_pressed(*action, self.buttons & (1 << i) != 0);
+        }
+        action_state.set_rotation(self.rotation);
+    }
+
+    fn same_as(&self, other: &TickInput) -> bool {
+        self.rotation.to_bits() == other.rotation.to_bits() && self.buttons == other.buttons
+    }
+}
+
+/// Everything needed to play a game again: the seed, the rules and every tick's input
+#[derive(Clone, PartialEq, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    /// Wave the game started at
+    pub wave: u32,
+    pub inputs: Vec<TickInput>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, difficulty: Difficulty, wave: u32) -> Replay {
+        Replay {
+            seed,
+            difficulty,
+            wave,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.inputs.len() as u32
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        Replay::decode(&bytes)
+    }
+
+    pub fn save(&self, path: &Path) {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(e) = std::fs::write(path, self.encode()) {
+            warn!("Could not save replay to {}: {}", path.display(), e);
+        }
+    }
+
+    /// Header, then the inputs run-length encoded: input rarely changes from one tick to the next
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.push(match self.difficulty {
+            Difficulty::Easy => 0,
+            Difficulty::Normal => 1,
+            Difficulty::Hard => 2,
+        });
+        bytes.extend_from_slice(&self.wave.to_le_bytes());
+        let mut runs: Vec<(u16, TickInput)> = Vec::new();
+        for input in self.inputs.iter() {
+            match runs.last_mut() {
+                Some((length, last)) if last.same_as(input) && *length < u16::MAX => *length += 1,
+                _ => runs.push((1, *input)),
+            }
+        }
+        bytes.extend_from_slice(&(runs.len() as u32).to_le_bytes());
+        for (length, input) in runs {
+            bytes.extend_from_slice(&length.to_le_bytes());
+            bytes.extend_from_slice(&input.rotation.to_le_bytes());
+            bytes.push(input.buttons);
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Replay, String> {
+        let mut rest = bytes;
+        if take::<4>(&mut rest)? != *MAGIC {
+            return Err("not a replay file".to_string());
+        }
+        let version = take::<1>(&mut rest)?[0];
+        if version != VERSION {
+            return Err(format!("replay version {} can't be played by this version of the game", version));
+        }
+        let seed = u64::from_le_bytes(take(&mut rest)?);
+        let difficulty = match take::<1>(&mut rest)?[0] {
+            0 => Difficulty::Easy,
+            1 => Difficulty::Normal,
+            2 => Difficulty::Hard,
+            other => return Err(format!("unknown difficulty {}", other)),
+        };
+        let wave = u32::from_le_bytes(take(&mut rest)?);
+        let runs = u32::from_le_bytes(take(&mut rest)?);
+        let mut inputs = Vec::new();
+        for _ in 0..runs {
+            let length = u16::from_le_bytes(take(&mut rest)?) as usize;
+            let input = TickInput {
+                rotation: f32::from_le_bytes(take(&mut rest)?),
+                buttons: take::<1>(&mut rest)?[0],
+            };
+            if length == 0 {
+                return Err("replay has an empty run of input".to_string());
+            }
+            if input.buttons >> RECORDED_ACTIONS.len() != 0 {
+                return Err(format!("unknown buttons {:#010b}", input.buttons));
+            }
+            if inputs.len() + length > MAX_TICKS {
+                return Err(format!("replay is longer than {} ticks", MAX_TICKS));
+            }
+            inputs.extend(std::iter::repeat_n(input, length));
+        }
+        if !rest.is_empty() {
+            return Err(format!("{} unexpected bytes after the input", rest.len()));
+        }
+        Ok(Replay {
+            seed,
+            difficulty,
+            wave,
+            inputs,
+        })
+    }
+}
+
+fn take<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N], String> {
+    if bytes.len() < N {
+        return Err("replay file is truncated".to_string());
+    }
+    let (head, tail) = bytes.split_at(N);
+    *bytes = tail;
+    Ok(head.try_into().unwrap())
+}
+
+pub fn replays_dir() -> PathBuf {
+    config_dir().join("replays")
+}
+
+/// The game currently being recorded, written to `replays_dir()` once it ends
+#[derive(Resource)]
+pub struct Recording {
+    pub replay: Option<Replay>,
+    save: bool,
+}
+
+impl Default for Recording {
+    fn default() -> Recording {
+        Recording {
+            replay: None,
+            save: true,
+        }
+    }
+}
+
+impl Recording {
+    /// Keeps recordings in memory only, for tests and headless runs
+    pub fn unsaved() -> Recording {
+        Recording {
+            replay: None,
+            save: false,
+        }
+    }
+}
+
+/// The replay being watched, if any
+#[derive(Resource, Default)]
+pub struct Playback {
+    replay: Option<Replay>,
+    seek: Option<u32>,
+    speed: usize,
+    /// The player's own difficulty and starting wave, put back once the replay is over
+    previous: Option<(Difficulty, u32)>,
+}
+
+impl Playback {
+    pub fn active(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    pub fn seeking(&self) -> bool {
+        self.seek.is_some()
+    }
+
+    /// Jumps to `tick`; going backwards restarts the game and simulates up to it again
+    pub fn seek(&mut self, tick: u32) {
+        if let Some(replay) = &self.replay {
+            self.seek = Some(tick.min(replay.ticks()));
+        }
+    }
+
+    /// Length of the replay being watched
+    pub fn ticks(&self) -> u32 {
+        self.replay.as_ref().map_or(0, |replay| replay.ticks())
+    }
+}
+
+pub fn playing(
+    playback: Res<Playback>,
+) -> bool {
+    playback.active()
+}
+
+/// Starts watching a replay from the beginning, from whatever state the game is in
+#[derive(Event)]
+pub struct StartReplay(pub Replay);
+
+#[derive(Component, Clone)]
+enum ReplayControl {
+    TogglePause,
+    Speed,
+    Exit,
+}
+
+#[derive(Component, Clone)]
+enum ReplaysButtonAction {
+    Open(PathBuf),
+    Back,
+}
+
+#[derive(Component)]
+struct ScrubBar;
+
+#[derive(Component)]
+struct ScrubFill;
+
+#[derive(Component)]
+struct ReplayClock;
+
+#[allow(clippy::too_many_arguments)]
+fn start_replay_system(
+    mut commands: Commands,
+    mut events: EventReader<StartReplay>,
+    mut playback: ResMut<Playback>,
+    mut settings: ResMut<Settings>,
+    mut wave: ResMut<Wave>,
+    mut rng: ResMut<GameRng>,
+    mut time: ResMut<Time<Virtual>>,
+    mut game_state: ResMut<NextState<GameState>>,
+    despawnable: Query<Entity, With<GameDespawnable>>,
+) {
+    let Some(StartReplay(replay)) = events.read().last() else {
+        return;
+    };
+    if playback.previous.is_none() {
+        playback.previous = Some((settings.difficulty, wave.start));
+    }
+    settings.difficulty = replay.difficulty;
+    wave.start = replay.wave;
+    *rng = GameRng::new(replay.seed);
+    playback.replay = Some(replay.clone());
+    playback.seek = None;
+    playback.speed = 0;
+    time.set_relative_speed_f64(SPEEDS[0]);
+    time.unpause();
+    for entity in despawnable.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    game_state.set(GameState::Loading);
+}
+
+fn start_recording(
+    mut recording: ResMut<Recording>,
+    playback: Res<Playback>,
+    rng: Res<GameRng>,
+    settings: Res<Settings>,
+    wave: Res<Wave>,
+) {
+    recording.replay = if playback.active() {
+        None
+    } else {
+        Some(Replay::new(rng.seed(), settings.difficulty, wave.start))
+    };
+}
+
+fn record_input_system(
+    mut recording: ResMut<Recording>,
+    action_state: Res<ActionState>,
+) {
+    if let Some(replay) = recording.replay.as_mut() {
+        // Past this the replay couldn't be loaded again, so the rest of a marathon game goes unrecorded
+        if replay.inputs.len() < MAX_TICKS {
+            replay.inputs.push(TickInput::read(&action_state));
+        }
+    }
+}
+
+pub fn replay_input_system(
+    playback: Res<Playback>,
+    tick: Res<SimulationTick>,
+    mut action_state: ResMut<ActionState>,
+) {
+    if let Some(replay) = &playback.replay {
+        let input = replay.inputs.get(tick.0 as usize).copied().unwrap_or_default();
+        input.apply(&mut action_state);
+    }
+}
+
+fn save_recording(
+    mut recording: ResMut<Recording>,
+) {
+    let Some(replay) = recording.replay.take() else {
+        return;
+    };
+    if recording.save && !replay.inputs.is_empty() {
+        let path = replays_dir().join(format!("replay-{}.mbdr", timestamp()));
+        info!("Saving replay to {}", path.display());
+        replay.save(&path);
+    }
+}
+
+fn stop_playback(
+    mut playback: ResMut<Playback>,
+    mut settings: ResMut<Settings>,
+    mut wave: ResMut<Wave>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if playback.replay.take().is_none() {
+        return;
+    }
+    if let Some((difficulty, start)) = playback.previous.take() {
+        settings.difficulty = difficulty;
+        wave.start = start;
+    }
+    playback.seek = None;
+    time.set_relative_speed_f64(SPEEDS[0]);
+    time.unpause();
+}
+
+/// e.g. "2026-10-19_14-03-22", in UTC
+fn timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+    // Days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", year, month, day, rem / 3600, rem / 60 % 60, rem % 60)
+}
+
+/// e.g. "02:30"
+fn clock(ticks: u32) -> String {
+    let secs = (ticks as f64 / TICK_RATE) as u32;
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+fn spawn_replay_bar(
+    mut commands: Commands,
+    playback: Res<Playback>,
+) {
+    if !playback.active() {
+        return;
+    }
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Px(60.0),
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.0),
+                padding: UiRect::horizontal(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+        GameDespawnable,
+    )).with_children(|parent| {
+        let buttons = [
+            ("Pause", ReplayControl::TogglePause, 100.0),
+            ("1x", ReplayControl::Speed, 60.0),
+            ("Exit", ReplayControl::Exit, 80.0),
+        ];
+        for (label, control, width) in buttons {
+            spawn_button(parent, label, control, button_style(Val::Px(width), Val::Px(45.0)), button_text_style(24.0));
+        }
+        parent.spawn((
+            NodeBundle {
+                style: Style {
+                    flex_grow: 1.0,
+                    height: Val::Px(14.0),
+                    ..default()
+                },
+                background_color: Color::rgb(0.25, 0.25, 0.25).into(),
+                ..default()
+            },
+            Interaction::default(),
+            RelativeCursorPosition::default(),
+            ScrubBar,
+        )).with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.35, 0.75, 0.35).into(),
+                    ..default()
+                },
+                ScrubFill,
+            ));
+        });
+        parent.spawn((
+            TextBundle::from_section("", button_text_style(24.0)),
+            ReplayClock,
+        ));
+    });
+}
+
+/// Buttons on the replay bar, plus Space to pause, F to change speed and ,/. to step 5s
+fn replay_control_system(
+    mut activated: EventReader<Activated<ReplayControl>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut playback: ResMut<Playback>,
+    tick: Res<SimulationTick>,
+    mut time: ResMut<Time<Virtual>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let mut controls: Vec<ReplayControl> = activated.read().map(|Activated(control)| control.clone()).collect();
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        controls.push(ReplayControl::TogglePause);
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyF) {
+        controls.push(ReplayControl::Speed);
+    }
+    for control in controls {
+        match control {
+            ReplayControl::TogglePause => {
+                if time.is_paused() {
+                    // Play again from the start once the end was reached
+                    if tick.0 >= playback.ticks() {
+                        playback.seek(0);
+                    }
+                    time.unpause();
+                } else {
+                    time.pause();
+                }
+            }
+            ReplayControl::Speed => {
+                playback.speed = (playback.speed + 1) % SPEEDS.len();
+                time.set_relative_speed_f64(SPEEDS[playback.speed]);
+            }
+            ReplayControl::Exit => {
+                game_state.set(GameState::Menu);
+            }
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Comma) {
+        playback.seek(tick.0.saturating_sub(SEEK_STEP));
+    }
+    if keyboard_input.just_pressed(KeyCode::Period) {
+        playback.seek(tick.0 + SEEK_STEP);
+    }
+    // Hold the last frame instead of running past the recorded input
+    if tick.0 >= playback.ticks() && !playback.seeking() {
+        time.pause();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn scrub_bar_system(
+    mut playback: ResMut<Playback>,
+    bars: Query<(&Interaction, &RelativeCursorPosition), (Changed<Interaction>, With<ScrubBar>)>,
+) {
+    for (interaction, cursor) in bars.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(position) = cursor.normalized {
+            let ticks = playback.ticks();
+            playback.seek((position.x.clamp(0.0, 1.0) * ticks as f32) as u32);
+        }
+    }
+}
+
+// Exclusive: fast-forwarding runs the fixed gameplay schedule directly, many ticks per frame
+fn seek_system(world: &mut World) {
+    let Some(target) = world.resource::<Playback>().seek else {
+        return;
+    };
+    if world.resource::<NextState<GameState>>().0.is_some() {
+        return;
+    }
+    let state = *world.resource::<State<GameState>>().get();
+    let tick = world.resource::<SimulationTick>().0;
+    if target < tick || (state == GameState::GameOver && target != tick) {
+        // The simulation can't run backwards, so start over and catch up
+        let entities: Vec<Entity> = world.query_filtered::<Entity, With<GameDespawnable>>().iter(world).collect();
+        for entity in entities {
+            world.entity_mut(entity).despawn_recursive();
+        }
+        world.resource_mut::<NextState<GameState>>().set(GameState::Loading);
+        return;
+    }
+    if state != GameState::Game {
+        return;
+    }
+    let timestep = world.resource::<Time<Fixed>>().timestep();
+    for _ in tick..target.min(tick + SEEK_TICKS_PER_FRAME) {
+        world.resource_mut::<Time<Fixed>>().advance_by(timestep);
+        *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+        world.run_schedule(FixedMain);
+        // Stop at game over, like the regular fixed update would on the next state transition
+        if world.resource::<NextState<GameState>>().0.is_some() {
+            break;
+        }
+    }
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+    if world.resource::<SimulationTick>().0 >= target || world.resource::<NextState<GameState>>().0.is_some() {
+        world.resource_mut::<Playback>().seek = None;
+    }
+}
+
+fn replay_bar_system(
+    playback: Res<Playback>,
+    tick: Res<SimulationTick>,
+    time: Res<Time<Virtual>>,
+    controls: Query<(&ReplayControl, &Children)>,
+    mut fills: Query<&mut Style, With<ScrubFill>>,
+    mut texts: Query<&mut Text>,
+    clocks: Query<Entity, With<ReplayClock>>,
+) {
+    let ticks = playback.ticks();
+    let shown = playback.seek.unwrap_or(tick.0).min(ticks);
+    for mut style in fills.iter_mut() {
+        style.width = Val::Percent(if ticks == 0 { 0.0 } else { shown as f32 / ticks as f32 * 100.0 });
+    }
+    for entity in clocks.iter() {
+        if let Ok(mut text) = texts.get_mut(entity) {
+            text.sections[0].value = format!("{} / {}", clock(shown), clock(ticks));
+        }
+    }
+    for (control, children) in controls.iter() {
+        let label = match control {
+            ReplayControl::TogglePause => if time.is_paused() { "Play".to_string() } else { "Pause".to_string() },
+            ReplayControl::Speed => format!("{}x", SPEEDS[playback.speed]),
+            ReplayControl::Exit => continue,
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Saved replays, newest first
+fn list_replays() -> Vec<(PathBuf, Replay)> {
+    let Ok(entries) = std::fs::read_dir(replays_dir()) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "mbdr"))
+        .collect();
+    paths.sort();
+    paths.reverse();
+    paths.into_iter()
+        .filter_map(|path| match Replay::load(&path) {
+            Ok(replay) => Some((path, replay)),
+            Err(e) => {
+                warn!("Skipping replay {}: {}", path.display(), e);
+                None
+            }
+        })
+        .take(MAX_LISTED)
+        .collect()
+}
+
+fn spawn_replays_menu(
+    mut commands: Commands,
+) {
+    let replays = list_replays();
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.15, 0.15, 0.15, 0.30).into(),
+            ..default()
+        },
+        ReplaysDespawnable,
+    )).with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Replays", TextStyle {
+            font_size: 50.0,
+            color: TEXT_COLOR,
+            ..default()
+        }));
+        if replays.is_empty() {
+            parent.spawn(TextBundle::from_section("No replays yet, play a game first", button_text_style(30.0)));
+        }
+        for (path, replay) in replays {
+            let name = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().trim_start_matches("replay-").to_string());
+            let label = format!("{}   {}   {}", name, clock(replay.ticks()), replay.difficulty.name().to_uppercase());
+            spawn_button(parent, &label, ReplaysButtonAction::Open(path), button_style(Val::Px(700.0), Val::Px(50.0)), button_text_style(28.0));
+        }
+        spawn_button(parent, "Back", ReplaysButtonAction::Back, button_style(Val::Px(150.0), Val::Px(65.0)), button_text_style(40.0));
+    });
+}
+
+fn replays_menu_action(
+    mut activated: EventReader<Activated<ReplaysButtonAction>>,
+    action_state: Res<ActionState>,
+    mut start_events: EventWriter<StartReplay>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if action_state.just_pressed(Action::Pause) {
+        game_state.set(GameState::Menu);
+    }
+    for Activated(action) in activated.read() {
+        match action {
+            ReplaysButtonAction::Open(path) => match Replay::load(path) {
+                Ok(replay) => {
+                    start_events.send(StartReplay(replay));
+                }
+                Err(e) => warn!("Could not open replay {}: {}", path.display(), e),
+            },
+            ReplaysButtonAction::Back => {
+                game_state.set(GameState::Menu);
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+use bevy::gizmos::config::GizmoConfigStore;
+use crate::gamestate::GameState;
+use crate::gamestate::despawn_system;
+use crate::gamestate::GameDespawnable;
+use crate::player::Player;
+use crate::sonar::Sonar;
+use crate::tracking::Track;
+use crate::tracking::Tracks;
+use crate::tracking::track_label;
+use crate::simulation::SimulationTick;
+use crate::simulation::heading;
+
+/// Range rings, bearing compass and contact readout drawn over the sonar scope, all derived from
+/// `Sonar::radius` every frame. The rings and ticks are gizmos, which only the windowed app has.
+pub struct ScopePlugin;
+impl Plugin for ScopePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Loading), spawn_scope_labels)
+            .add_systems(OnEnter(GameState::Loading), spawn_contact_readout)
+            .add_systems(Update, scope_gizmo_system.run_if(in_state(GameState::Game)).run_if(resource_exists::<GizmoConfigStore>))
+            .add_systems(Update, scope_label_system.run_if(in_state(GameState::Game)))
+            .add_systems(Update, contact_readout_system.run_if(in_state(GameState::Game)))
+            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>);
+    }
+}
+
+/// Number of range rings, evenly spaced out to the edge of the scope
+pub const RANGE_RINGS: u32 = 4;
+/// Degrees between compass ticks, every third one is labelled
+const TICK_STEP: u32 = 10;
+const LABEL_STEP: u32 = 30;
+
+const SCOPE_COLOR: Color = Color::rgba(0.0, 1.0, 0.0, 0.35);
+const READOUT_COLOR: Color = Color::rgb(0.0, 0.9, 0.3);
+
+/// Where a label sits on the scope
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub enum ScopeLabel {
+    /// Compass bearing in degrees, on the rim
+    Bearing(u32),
+    /// Ring number counting out from the center, labelled with its range
+    Range(u32),
+}
+
+#[derive(Component)]
+pub struct ContactReadout;
+
+/// Radians counter-clockwise from up, as sprites and `heading` use, to compass degrees clockwise from north
+pub fn compass(heading: f32) -> f32 {
+    // Adding zero turns -0.0 into 0.0 so due north doesn't print with a sign
+    (-heading.to_degrees()).rem_euclid(360.0) + 0.0
+}
+
+/// Own heading in compass degrees
+pub fn own_heading(transform: &Transform) -> f32 {
+    compass(heading((transform.rotation * Vec3::Y).xy()))
+}
+
+/// What the readout shows about a contact seen from `from`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ContactReading {
+    /// Compass degrees to the contact
+    pub bearing: f32,
+    pub range: f32,
+    /// How fast the range is shrinking, in units per second, negative when the contact opens
+    pub closure_rate: f32,
+}
+
+impl ContactReading {
+    pub fn new(from: Vec2, track: &Track, tick: u32) -> ContactReading {
+        let offset = track.estimated_position(tick) - from;
+        let line_of_sight = offset.normalize_or_zero();
+        ContactReading {
+            bearing: compass(heading(offset)),
+            range: offset.length(),
+            closure_rate: -track.velocity.dot(line_of_sight),
+        }
+    }
+}
+
+fn spawn_scope_labels(
+    mut commands: Commands,
+) {
+    let labels = (0..360).step_by(LABEL_STEP as usize).map(ScopeLabel::Bearing)
+        .chain((1..RANGE_RINGS).map(ScopeLabel::Range));
+    for label in labels {
+        let text = match label {
+            ScopeLabel::Bearing(degrees) => format!("{:03}", degrees),
+            // Set by scope_label_system once the radius is known
+            ScopeLabel::Range(_) => String::new(),
+        };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(text, TextStyle {
+                    font_size: 14.0,
+                    color: SCOPE_COLOR,
+                    ..default()
+                }),
+                transform: Transform::from_xyz(0.0, 0.0, -1.5),
+                ..default()
+            },
+            label,
+            GameDespawnable,
+        ));
+    }
+}
+
+fn spawn_contact_readout(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: READOUT_COLOR,
+                ..default()
+            },
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        ContactReadout,
+        GameDespawnable,
+    ));
+}
+
+fn scope_gizmo_system(
+    mut gizmos: Gizmos,
+    sonar_query: Query<(&Sonar, &Transform)>,
+) {
+    let Ok((sonar, transform)) = sonar_query.get_single() else { return; };
+    let center = transform.translation.xy();
+    for ring in 1..=RANGE_RINGS {
+        gizmos.circle_2d(center, sonar.radius * ring as f32 / RANGE_RINGS as f32, SCOPE_COLOR).segments(64);
+    }
+    for degrees in (0..360).step_by(TICK_STEP as usize) {
+        let length = if degrees % LABEL_STEP == 0 { 0.06 } else { 0.03 };
+        let direction = Vec2::from_angle(-(degrees as f32).to_radians()).rotate(Vec2::Y);
+        gizmos.line_2d(center + direction * sonar.radius * (1.0 - length), center + direction * sonar.radius, SCOPE_COLOR);
+    }
+}
+
+fn scope_label_system(
+    sonar_query: Query<(&Sonar, &Transform), Without<ScopeLabel>>,
+    mut label_query: Query<(&ScopeLabel, &mut Transform, &mut Text)>,
+) {
+    let Ok((sonar, sonar_transform)) = sonar_query.get_single() else { return; };
+    let center = sonar_transform.translation.xy();
+    for (label, mut transform, mut text) in label_query.iter_mut() {
+        let position = match *label {
+            ScopeLabel::Bearing(degrees) => {
+                let direction = Vec2::from_angle(-(degrees as f32).to_radians()).rotate(Vec2::Y);
+                center + direction * sonar.radius * 0.88
+            }
+            ScopeLabel::Range(ring) => {
+                let range = sonar.radius * ring as f32 / RANGE_RINGS as f32;
+                let value = format!("{:.0}", range);
+                if text.sections[0].value != value {
+                    text.sections[0].value = value;
+                }
+                // Just right of where the ring crosses due north
+                center + Vec2::new(14.0, range - 8.0)
+            }
+        };
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+fn contact_readout_system(
+    tracks: Res<Tracks>,
+    tick: Res<SimulationTick>,
+    player_query: Query<&Transform, With<Player>>,
+    sonar_query: Query<&Sonar>,
+    mut text_query: Query<&mut Text, With<ContactReadout>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let Ok(mut text) = text_query.get_single_mut() else { return; };
+    let mut value = format!("OWN HDG {:03.0}", own_heading(player_transform).round() % 360.0);
+    if let Ok(sonar) = sonar_query.get_single() {
+        value.push_str(&format!(" SONAR {}", sonar.mode.label()));
+    }
+    match tracks.selected() {
+        Some(track) => {
+            let reading = ContactReading::new(player_transform.translation.xy(), track, tick.0);
+            value.push_str(&format!(
+                "\n{} BRG {:03.0} RNG {:.0} CLS {:+.1}",
+                track_label(track),
+                reading.bearing.round() % 360.0,
+                reading.range,
+                reading.closure_rate,
+            ));
+        }
+        None => value.push_str("\nNO CONTACT SELECTED"),
+    }
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
@@ -0,0 +1,522 @@
+use bevy::prelude::*;
+use bevy::window::*;
+use crate::gamestate::GameState;
+use crate::gamestate::despawn_system;
+use crate::gamestate::SettingsDespawnable;
+use crate::sound::AudioSettings;
+use crate::actions::Action;
+use crate::actions::ActionState;
+use crate::actions::Bindings;
+use crate::actions::is_bindable;
+use crate::widgets::Activated;
+use crate::widgets::TEXT_COLOR;
+use crate::widgets::button_activation_system;
+use crate::widgets::button_style;
+use crate::widgets::button_text_style;
+use std::path::PathBuf;
+
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SettingsReturn(GameState::Menu))
+            .init_resource::<Rebinding>()
+            .add_event::<Activated<SettingsButtonAction>>()
+            .add_systems(Update, apply_settings_system.run_if(resource_changed::<Settings>))
+            .add_systems(Update, settings_text_system.run_if(in_state(GameState::Settings)))
+            .add_systems(Update, (
+                    button_activation_system::<SettingsButtonAction>,
+                    settings_action,
+                    back_system,
+                    rebind_system,
+                ).chain().run_if(in_state(GameState::Settings)))
+            .add_systems(OnEnter(GameState::Settings), spawn_settings_menu)
+            .add_systems(OnExit(GameState::Settings), (despawn_system::<SettingsDespawnable>, save_settings));
+    }
+}
+
+pub const RESOLUTIONS: [(f32, f32); 4] = [
+    (1280.0, 720.0),
+    (1600.0, 900.0),
+    (1920.0, 1080.0),
+    (2560.0, 1440.0),
+];
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn enemy_count(&self) -> usize {
+        match self {
+            Difficulty::Easy => 3,
+            Difficulty::Normal => 5,
+            Difficulty::Hard => 8,
+        }
+    }
+
+    /// Every how many enemy torpedoes one is guided, `None` for only straight runners
+    pub fn guided_every(&self) -> Option<u32> {
+        match self {
+            Difficulty::Easy => None,
+            Difficulty::Normal => Some(3),
+            Difficulty::Hard => Some(2),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+}
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct Settings {
+    pub resolution: (f32, f32),
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub difficulty: Difficulty,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        let audio = AudioSettings::default();
+        Settings {
+            resolution: (1920.0, 1080.0),
+            fullscreen: false,
+            vsync: true,
+            master_volume: audio.master,
+            sfx_volume: audio.sfx,
+            music_volume: audio.music,
+            difficulty: Difficulty::Normal,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the config file, falling back to defaults for anything missing or malformed
+    pub fn load() -> Settings {
+        match std::fs::read_to_string(config_path()) {
+            Ok(contents) => Settings::parse(&contents),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let path = config_path();
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(e) = std::fs::write(&path, self.serialize()) {
+            warn!("Could not save settings to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn parse(contents: &str) -> Settings {
+        let mut settings = Settings::default();
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else { continue; };
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "resolution" => {
+                    if let Some((w, h)) = value.split_once('x') {
+                        if let (Ok(w), Ok(h)) = (w.parse::<f32>(), h.parse::<f32>()) {
+                            // Anything else would be handed straight to the window
+                            if w.is_finite() && h.is_finite() && w > 0.0 && h > 0.0 {
+                                settings.resolution = (w, h);
+                            }
+                        }
+                    }
+                }
+                "fullscreen" => settings.fullscreen = value.parse().unwrap_or(settings.fullscreen),
+                "vsync" => settings.vsync = value.parse().unwrap_or(settings.vsync),
+                "master_volume" => settings.master_volume = parse_volume(value, settings.master_volume),
+                "sfx_volume" => settings.sfx_volume = parse_volume(value, settings.sfx_volume),
+                "music_volume" => settings.music_volume = parse_volume(value, settings.music_volume),
+                "difficulty" => settings.difficulty = Difficulty::from_name(value).unwrap_or(settings.difficulty),
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    pub fn serialize(&self) -> String {
+        format!(
+            "resolution = {}x{}\nfullscreen = {}\nvsync = {}\nmaster_volume = {:.1}\nsfx_volume = {:.1}\nmusic_volume = {:.1}\ndifficulty = {}\n",
+            self.resolution.0, self.resolution.1,
+            self.fullscreen,
+            self.vsync,
+            self.master_volume,
+            self.sfx_volume,
+            self.music_volume,
+            self.difficulty.name(),
+        )
+    }
+
+    pub fn window(&self) -> Window {
+        let mut window = Window {
+            title: "Miles Below Darkness".to_string(),
+            resolution: WindowResolution::new(self.resolution.0, self.resolution.1).with_scale_factor_override(1.0),
+            ..default()
+        };
+        self.apply_to_window(&mut window);
+        window
+    }
+
+    fn apply_to_window(&self, window: &mut Window) {
+        if window.resolution.width() != self.resolution.0 || window.resolution.height() != self.resolution.1 {
+            window.resolution.set(self.resolution.0, self.resolution.1);
+        }
+        window.mode = if self.fullscreen { WindowMode::BorderlessFullscreen } else { WindowMode::Windowed };
+        window.present_mode = if self.vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync };
+    }
+}
+
+fn parse_volume(value: &str, fallback: f32) -> f32 {
+    value.parse::<f32>().map_or(fallback, |v| v.clamp(0.0, 1.0))
+}
+
+/// Directory holding the config files, falling back to the working directory
+pub fn config_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    match base {
+        Some(dir) => dir.join("miles-below-darkness"),
+        None => PathBuf::from("."),
+    }
+}
+
+fn config_path() -> PathBuf {
+    config_dir().join("settings.cfg")
+}
+
+/// State to go back to when leaving the settings screen
+#[derive(Resource)]
+pub struct SettingsReturn(pub GameState);
+
+/// Action waiting for the next key press to be rebound
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum Volume {
+    Master,
+    Sfx,
+    Music,
+}
+
+/// What a button on the settings screen does
+#[derive(Component, Clone, Copy)]
+pub enum SettingsButtonAction {
+    Resolution,
+    Fullscreen,
+    Vsync,
+    VolumeDown(Volume),
+    VolumeUp(Volume),
+    Difficulty,
+    Rebind(Action),
+    Back,
+}
+
+#[derive(Component, Clone, Copy)]
+enum SettingsText {
+    Resolution,
+    Fullscreen,
+    Vsync,
+    Volume(Volume),
+    Difficulty,
+    Binding(Action),
+}
+
+const BACKGROUND: Color = Color::rgba(0.15, 0.15, 0.15, 0.30);
+
+fn spawn_settings_menu(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            background_color: BACKGROUND.into(),
+            ..default()
+        },
+        SettingsDespawnable,
+    )).with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                column_gap: Val::Px(40.0),
+                ..default()
+            },
+            ..default()
+        }).with_children(|parent| {
+            spawn_column(parent, |parent| {
+                spawn_row(parent, "Resolution", SettingsText::Resolution, |row| {
+                    spawn_button(row, "Change", SettingsButtonAction::Resolution);
+                });
+                spawn_row(parent, "Fullscreen", SettingsText::Fullscreen, |row| {
+                    spawn_button(row, "Toggle", SettingsButtonAction::Fullscreen);
+                });
+                spawn_row(parent, "VSync", SettingsText::Vsync, |row| {
+                    spawn_button(row, "Toggle", SettingsButtonAction::Vsync);
+                });
+                for (label, volume) in [("Master", Volume::Master), ("SFX", Volume::Sfx), ("Music", Volume::Music)] {
+                    spawn_row(parent, label, SettingsText::Volume(volume), |row| {
+                        spawn_button(row, "-", SettingsButtonAction::VolumeDown(volume));
+                        spawn_button(row, "+", SettingsButtonAction::VolumeUp(volume));
+                    });
+                }
+                spawn_row(parent, "Difficulty", SettingsText::Difficulty, |row| {
+                    spawn_button(row, "Change", SettingsButtonAction::Difficulty);
+                });
+            });
+            spawn_column(parent, |parent| {
+                for action in Action::ALL {
+                    spawn_row(parent, action.label(), SettingsText::Binding(action), |row| {
+                        spawn_button(row, "Rebind", SettingsButtonAction::Rebind(action));
+                    });
+                }
+            });
+        });
+        spawn_button(parent, "Back", SettingsButtonAction::Back);
+    });
+}
+
+fn spawn_column(
+    parent: &mut ChildBuilder,
+    rows: impl FnOnce(&mut ChildBuilder),
+) {
+    parent.spawn(NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(10.0),
+            ..default()
+        },
+        ..default()
+    }).with_children(rows);
+}
+
+fn spawn_row(
+    parent: &mut ChildBuilder,
+    label: &str,
+    text: SettingsText,
+    buttons: impl FnOnce(&mut ChildBuilder),
+) {
+    parent.spawn(NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(10.0),
+            ..default()
+        },
+        ..default()
+    }).with_children(|row| {
+        row.spawn(
+            TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 30.0,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ).with_style(Style {
+                width: Val::Px(200.0),
+                ..default()
+            })
+        );
+        row.spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 30.0,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ).with_style(Style {
+                width: Val::Px(180.0),
+                ..default()
+            }),
+            text,
+        ));
+        buttons(row);
+    });
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    label: &str,
+    action: SettingsButtonAction,
+) {
+    crate::widgets::spawn_button(
+        parent,
+        label,
+        action,
+        Style {
+            min_width: Val::Px(50.0),
+            padding: UiRect::horizontal(Val::Px(10.0)),
+            ..button_style(Val::Auto, Val::Px(50.0))
+        },
+        button_text_style(30.0),
+    );
+}
+
+fn settings_action(
+    mut activated: EventReader<Activated<SettingsButtonAction>>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    settings_return: Res<SettingsReturn>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for Activated(action) in activated.read() {
+        // Enter while waiting for a key is meant for rebind_system, not the focused button
+        if rebinding.0.is_some() {
+            continue;
+        }
+        match *action {
+            SettingsButtonAction::Resolution => {
+                let current = RESOLUTIONS.iter().position(|r| *r == settings.resolution);
+                let next = current.map_or(0, |i| (i + 1) % RESOLUTIONS.len());
+                settings.resolution = RESOLUTIONS[next];
+            }
+            SettingsButtonAction::Fullscreen => {
+                settings.fullscreen = !settings.fullscreen;
+            }
+            SettingsButtonAction::Vsync => {
+                settings.vsync = !settings.vsync;
+            }
+            SettingsButtonAction::VolumeDown(volume) => {
+                let value = volume_mut(&mut settings, volume);
+                *value = ((*value - 0.1) * 10.0).round().max(0.0) / 10.0;
+            }
+            SettingsButtonAction::VolumeUp(volume) => {
+                let value = volume_mut(&mut settings, volume);
+                *value = ((*value + 0.1) * 10.0).round().min(10.0) / 10.0;
+            }
+            SettingsButtonAction::Difficulty => {
+                settings.difficulty = settings.difficulty.next();
+            }
+            SettingsButtonAction::Rebind(action) => {
+                rebinding.0 = Some(action);
+            }
+            SettingsButtonAction::Back => {
+                game_state.set(settings_return.0);
+            }
+        }
+    }
+}
+
+fn volume_mut(settings: &mut Settings, volume: Volume) -> &mut f32 {
+    match volume {
+        Volume::Master => &mut settings.master_volume,
+        Volume::Sfx => &mut settings.sfx_volume,
+        Volume::Music => &mut settings.music_volume,
+    }
+}
+
+fn settings_text_system(
+    settings: Res<Settings>,
+    bindings: Res<Bindings>,
+    rebinding: Res<Rebinding>,
+    mut text_query: Query<(&mut Text, &SettingsText)>,
+) {
+    for (mut text, kind) in text_query.iter_mut() {
+        let value = match *kind {
+            SettingsText::Resolution => format!("{}x{}", settings.resolution.0, settings.resolution.1),
+            SettingsText::Fullscreen => if settings.fullscreen { "On" } else { "Off" }.to_string(),
+            SettingsText::Vsync => if settings.vsync { "On" } else { "Off" }.to_string(),
+            SettingsText::Volume(Volume::Master) => format!("{:.0}%", settings.master_volume * 100.0),
+            SettingsText::Volume(Volume::Sfx) => format!("{:.0}%", settings.sfx_volume * 100.0),
+            SettingsText::Volume(Volume::Music) => format!("{:.0}%", settings.music_volume * 100.0),
+            SettingsText::Difficulty => settings.difficulty.name().to_uppercase(),
+            SettingsText::Binding(action) if rebinding.0 == Some(action) => "press a key".to_string(),
+            SettingsText::Binding(action) => bindings.describe(action),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+fn back_system(
+    action_state: Res<ActionState>,
+    rebinding: Res<Rebinding>,
+    settings_return: Res<SettingsReturn>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if rebinding.0.is_none() && action_state.just_pressed(Action::Pause) {
+        game_state.set(settings_return.0);
+    }
+}
+
+fn rebind_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<Bindings>,
+) {
+    let Some(action) = rebinding.0 else { return; };
+    // Started this frame, so whatever is just pressed is the key that chose the Rebind button
+    if rebinding.is_changed() {
+        return;
+    }
+    if let Some(key) = keyboard_input.get_just_pressed().copied().find(|key| is_bindable(*key)) {
+        // Otherwise keep waiting for a key that doesn't leave pause unbound
+        if bindings.rebind(action, key) {
+            rebinding.0 = None;
+        }
+    }
+}
+
+fn apply_settings_system(
+    settings: Res<Settings>,
+    mut audio_settings: ResMut<AudioSettings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    audio_settings.master = settings.master_volume;
+    audio_settings.sfx = settings.sfx_volume;
+    audio_settings.music = settings.music_volume;
+    if let Ok(mut window) = windows.get_single_mut() {
+        settings.apply_to_window(&mut window);
+    }
+}
+
+fn save_settings(
+    settings: Res<Settings>,
+    bindings: Res<Bindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    rebinding.0 = None;
+    settings.save();
+    bindings.save();
+}
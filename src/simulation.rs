@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use bevy::ecs::schedule::ExecutorKind;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::gamestate::GameState;
+use crate::loading::RequestAssets;
+
+/// Gameplay ticks per second, independent of the frame rate
+pub const TICK_RATE: f64 = 60.0;
+
+pub struct SimulationPlugin;
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(TICK_RATE))
+            .init_resource::<GameRng>()
+            .init_resource::<SimulationTick>()
+            .configure_sets(FixedUpdate, (
+                SimulationSet::Input,
+                SimulationSet::Ai,
+                SimulationSet::Movement,
+                SimulationSet::Collision,
+                SimulationSet::Damage,
+            ).chain())
+            .add_systems(OnEnter(GameState::Loading), reseed_rng.before(RequestAssets))
+            .add_systems(FixedUpdate, advance_tick.after(SimulationSet::Damage).run_if(in_state(GameState::Game)));
+        // The multi-threaded executor runs unordered systems in whatever order threads free up,
+        // which would make two runs with the same seed drift apart
+        app.edit_schedule(FixedUpdate, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        });
+    }
+}
+
+/// Stages of one gameplay tick in `FixedUpdate`, run in this order
+#[derive(SystemSet, Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum SimulationSet {
+    /// Settling the player's controls for this tick, see `replay`
+    Input,
+    /// Decisions: where enemies head, who fires
+    Ai,
+    Movement,
+    /// Finding overlapping hitboxes, sends `Collision` events
+    Collision,
+    /// Reacting to collisions: damage, sonar pings, timers
+    Damage,
+}
+
+/// The only source of randomness gameplay systems may use, so a run can be replayed from its seed.
+/// Reset to its seed every time a game starts loading.
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng {
+    seed: u64,
+    #[deref]
+    rng: StdRng,
+    /// Keep the seed for every game instead of rolling a new one, see `reroll`
+    pinned: bool,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> GameRng {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            pinned: false,
+        }
+    }
+
+    /// A seed every game of the session is played with, e.g. from the command line
+    pub fn pinned(seed: u64) -> GameRng {
+        GameRng {
+            pinned: true,
+            ..GameRng::new(seed)
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Picks a fresh random seed for the next game, unless the seed is pinned
+    pub fn reroll(&mut self) {
+        if !self.pinned {
+            *self = GameRng::default();
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> GameRng {
+        GameRng::new(rand::random())
+    }
+}
+
+/// Ticks simulated since the current game finished loading
+#[derive(Resource, Default, Clone, Copy, Deref)]
+pub struct SimulationTick(pub u32);
+
+fn reseed_rng(
+    mut rng: ResMut<GameRng>,
+    mut tick: ResMut<SimulationTick>,
+) {
+    info!("Game seed: {}", rng.seed);
+    rng.rng = StdRng::seed_from_u64(rng.seed);
+    tick.0 = 0;
+}
+
+fn advance_tick(
+    mut tick: ResMut<SimulationTick>,
+) {
+    tick.0 += 1;
+}
+
+/// Rotation that points a sprite's up along `direction`
+pub fn heading(direction: Vec2) -> f32 {
+    (-direction.x).atan2(direction.y)
+}
@@ -1,32 +1,123 @@
 use bevy::prelude::*;
-use bevy::window::*;
 use bevy::sprite::MaterialMesh2dBundle;
+use bevy::sprite::Mesh2dHandle;
 use crate::gamestate::GameState;
 use crate::gamestate::despawn_system;
 use crate::gamestate::GameDespawnable;
-use crate::hitbox::Hitbox;
-use crate::hitbox::Collision;
+use crate::gamestate::PlayArea;
+use crate::player::Player;
+use crate::player::Compartments;
+use crate::player::Subsystem;
+use crate::player::player_rotation_system;
+use crate::player::compartment_repair_system;
+use crate::enemy::next_wave_system;
+use crate::actions::Action;
+use crate::actions::ActionState;
+use crate::simulation::heading;
+use crate::tracking::Classification;
+use crate::layout::Anchor;
+use crate::loading::GameAssets;
+use crate::loading::RequestAssets;
+use crate::simulation::GameRng;
+use crate::simulation::SimulationSet;
+use crate::console::ConsoleCommandsExt;
+use crate::console::ConsoleResult;
+use crate::console::console_arg;
+use crate::diagnostics::FADE_AWAY_SYSTEM_TIME;
+use bevy::diagnostic::Diagnostics;
+use bevy::utils::Instant;
 use std::time::Duration;
+use rand::Rng;
 
 pub struct SonarPlugin;
 impl Plugin for SonarPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Loading), setup_sonar)
-            .add_systems(Update, line_spin_system.run_if(in_state(GameState::Game)))
-            .add_systems(Update, ping_system.run_if(in_state(GameState::Game)))
-            .add_systems(Update, fade_away_system.run_if(in_state(GameState::Game)))
-            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>);
+        app.add_event::<PingEvent>()
+            .init_resource::<SonarNoise>()
+            .add_systems(OnEnter(GameState::Loading), setup_sonar.after(RequestAssets))
+            .add_systems(FixedUpdate, (sonar_mode_system, line_spin_system).chain().in_set(SimulationSet::Movement).after(player_rotation_system).run_if(in_state(GameState::Game)))
+            // After the other systems drawing from GameRng in this set, so they always draw in the same order
+            .add_systems(FixedUpdate, (ping_system, fade_away_system, echo_system).chain().in_set(SimulationSet::Damage).after(compartment_repair_system).after(next_wave_system).run_if(in_state(GameState::Game)))
+            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>)
+            .add_console_command("sweep", "<speed>", "Set the sweep line speed in radians per second", sweep_command)
+            .add_console_command("noise", "<on|off>", "Turn sonar noise and false contacts on or off", noise_command);
     }
 }
 
+/// Degrees of the arc a sector scan sweeps, centered on the bow
+pub const SECTOR_WIDTH: f32 = 90.0;
+
 #[derive(Component)]
 pub struct Sonar {
+    /// Edge of the scope, the farthest any mode reaches
     pub radius: f32,
+    pub mode: SonarMode,
+}
+
+impl Sonar {
+    /// How far the sweep reaches in the current mode
+    pub fn range(&self) -> f32 {
+        self.radius * self.mode.range()
+    }
+}
+
+/// How the sweep line covers the scope, trading reach against how often a contact is revisited
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum SonarMode {
+    /// Whole scope, slow turns
+    #[default]
+    LongRange,
+    /// Inner half of the scope, fast turns
+    ShortRange,
+    /// Whole scope, back and forth across `SECTOR_WIDTH` around the bow
+    Sector,
+}
+
+impl SonarMode {
+    pub fn next(self) -> SonarMode {
+        match self {
+            SonarMode::LongRange => SonarMode::ShortRange,
+            SonarMode::ShortRange => SonarMode::Sector,
+            SonarMode::Sector => SonarMode::LongRange,
+        }
+    }
+
+    /// Fraction of the scope radius the sweep reaches
+    pub fn range(self) -> f32 {
+        match self {
+            SonarMode::LongRange => 1.0,
+            SonarMode::ShortRange => 0.5,
+            SonarMode::Sector => 1.0,
+        }
+    }
+
+    /// Radians per second
+    pub fn sweep_speed(self) -> f32 {
+        match self {
+            SonarMode::LongRange => 1.25,
+            SonarMode::ShortRange => 4.0,
+            SonarMode::Sector => 2.0,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SonarMode::LongRange => "LONG",
+            SonarMode::ShortRange => "SHORT",
+            SonarMode::Sector => "SECTOR",
+        }
+    }
 }
 
 #[derive(Component)]
 pub struct Line {
     rotation_speed: f32,
+    /// Clockwise from north, in radians
+    angle: f32,
+    /// 1.0 while turning clockwise, -1.0 on the way back of a sector scan
+    direction: f32,
+    /// Radians covered during the last tick, ending at `angle`, negative when turning counter-clockwise
+    swept: f32,
 }
 
 #[derive(Component)]
@@ -36,6 +127,77 @@ pub struct Pingable {
     fade_away: Duration,
 }
 
+/// Sent when the sweep passes over a contact
+#[derive(Event)]
+pub struct PingEvent {
+    pub entity: Entity,
+    pub position: Vec3,
+    /// What the echo sounded like, `None` for clutter
+    pub signature: Option<Signature>,
+}
+
+/// What a contact sounds like, which is how tracking tells contacts apart
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Signature {
+    pub class: Classification,
+    /// How readily it gives itself away, at 1.0 it is classified after `CLASSIFY_SECONDS` on track
+    pub strength: f32,
+}
+
+impl Signature {
+    pub const ENEMY_SUB: Signature = Signature { class: Classification::EnemySub, strength: 1.0 };
+    pub const TORPEDO: Signature = Signature { class: Classification::Torpedo, strength: 3.0 };
+    pub const WHALE: Signature = Signature { class: Classification::Biologic, strength: 1.5 };
+    pub const SCHOOL: Signature = Signature { class: Classification::Biologic, strength: 1.0 };
+    pub const DECOY: Signature = Signature { class: Classification::Decoy, strength: 2.0 };
+}
+
+/// How imperfect sonar returns are
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct SonarNoise {
+    /// False blips per full turn of the sweep
+    pub false_contacts: f32,
+    /// Largest error in a reported position, as a fraction of the contact's distance
+    pub jitter: f32,
+    /// Chance of missing a contact at the edge of the range, shrinking with the square of the distance closer in
+    pub falloff: f32,
+}
+
+impl Default for SonarNoise {
+    fn default() -> SonarNoise {
+        SonarNoise {
+            false_contacts: 1.0,
+            jitter: 0.04,
+            falloff: 0.4,
+        }
+    }
+}
+
+impl SonarNoise {
+    /// Perfect returns
+    pub const NONE: SonarNoise = SonarNoise {
+        false_contacts: 0.0,
+        jitter: 0.0,
+        falloff: 0.0,
+    };
+
+    /// Chance a contact `fraction` of the way out to the edge of the range is detected when swept
+    pub fn detection_chance(&self, fraction: f32) -> f32 {
+        (1.0 - self.falloff * fraction * fraction).clamp(0.0, 1.0)
+    }
+}
+
+/// What the scope shows of a ping: the same blip for every contact, at the position sonar reported rather
+/// than where the contact really is. Contacts with a `Signature` are never drawn themselves.
+#[derive(Component)]
+pub struct Echo {
+    lifetime: Timer,
+}
+
+/// An echo with nothing behind it
+#[derive(Component)]
+pub struct FalseContact;
+
 impl Pingable {
     pub fn pinged(&self) -> Pingable {
         Pingable {
@@ -44,8 +206,10 @@ impl Pingable {
             fade_away: self.fade_away,
         }
     }
+}
 
-    pub fn default() -> Pingable {
+impl Default for Pingable {
+    fn default() -> Pingable {
         Pingable {
             timer: Timer::new(Duration::from_millis(0), TimerMode::Once), //duration will be auto-set
             keep: Duration::from_millis(250),
@@ -54,122 +218,247 @@ impl Pingable {
     }
 }
 
+fn noise_command(
+    In(args): In<Vec<String>>,
+    mut noise: ResMut<SonarNoise>,
+) -> ConsoleResult {
+    let on: String = console_arg(&args, 0, "on|off")?;
+    *noise = match on.as_str() {
+        "on" => SonarNoise::default(),
+        "off" => SonarNoise::NONE,
+        _ => return Err(format!("expected on or off, got '{}'", on)),
+    };
+    Ok(format!("sonar noise {}", on))
+}
+
+fn sweep_command(
+    In(args): In<Vec<String>>,
+    mut line_query: Query<&mut Line>,
+) -> ConsoleResult {
+    let speed: f32 = console_arg(&args, 0, "speed")?;
+    let mut line = line_query.get_single_mut().map_err(|_| "there is no sonar".to_string())?;
+    line.rotation_speed = speed;
+    Ok(format!("sweep speed set to {}", speed))
+}
+
 pub fn setup_sonar(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    play_area: Res<PlayArea>,
 ) {
-    let window = windows.single_mut();
-    let radius = window.resolution.height() / 2.0;
-    let texture_handle = asset_server.load("../assets/radar.png");
-    let texture_handle2 = asset_server.load("../assets/painel.png");
-    let texture_height = 857.0; //yes, this is the hardcoded sprite's height in px's (dont @ me)
-    let background_height = 1024.0; 
-    let background_width = 1792.0;
+    let radius = play_area.height / 2.0;
+    let mode = SonarMode::default();
+    let range = radius * mode.range();
+    let texture_handle = assets.radar.clone();
+    let texture_handle2 = assets.panel.clone();
     let (x, y) = (0.0, 0.0);
-    let painel_center_x = (window.resolution.width() / 2.0) - 1000.0;
-    let painel_center_y = (window.resolution.height() / 2.0) - 750.0;
-    let scale_factor_x = (window.resolution.width() / background_width) * 1.7; 
-    let scale_factor_y = (window.resolution.height() / background_height) * 1.7; 
-    
+
+    // Scale and position of both sprites are set by layout_system once their images load
     commands.spawn((
         SpriteBundle {
             texture: texture_handle2,
-            transform: Transform {
-                translation: Vec3::new(painel_center_x, painel_center_y, -3.0),
-                scale: Vec3::new(scale_factor_x, scale_factor_y, 1.0), 
-                ..default()
-            },
+            transform: Transform::from_xyz(x, y, -3.0),
             ..default()
         },
+        Anchor::SonarPanel,
         GameDespawnable,
     ));
     commands.spawn((
         SpriteBundle {
             texture: texture_handle,
-            transform: Transform {
-                translation: Vec3::new(x, y, -2.0),
-                scale: Vec3::splat(radius * 2.0 / texture_height),
-                ..default()
-            },
+            transform: Transform::from_xyz(x, y, -2.0),
             ..default()
         },
         Sonar {
-            radius: radius,
+            radius,
+            mode,
         },
+        Anchor::SonarScope,
         GameDespawnable,
     ));
     commands.spawn((
         MaterialMesh2dBundle {
-            mesh: meshes.add(Rectangle::new(2.0, radius)).into(),
+            mesh: meshes.add(Rectangle::new(2.0, range)).into(),
             material: materials.add(Color::GREEN),
             transform: Transform::from_translation(Vec3::Z * -1.0), //line_spin_system() will set x & y for us
             ..default()
         },
         Line {
-            rotation_speed: 2.0,
+            rotation_speed: mode.sweep_speed(),
+            angle: 0.0,
+            direction: 1.0,
+            swept: 0.0,
         },
-        Hitbox::new(2.0, radius),
         GameDespawnable,
     ));
 }
 
+// Runs on the tick so recorded input replays the same mode changes
+fn sonar_mode_system(
+    action_state: Res<ActionState>,
+    mut sonar_query: Query<&mut Sonar>,
+    mut line_query: Query<(&mut Line, &Mesh2dHandle)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if !action_state.tick_just_pressed(Action::SonarMode) {
+        return;
+    }
+    let Ok(mut sonar) = sonar_query.get_single_mut() else { return; };
+    sonar.mode = sonar.mode.next();
+    let range = sonar.range();
+    for (mut line, mesh) in line_query.iter_mut() {
+        line.rotation_speed = sonar.mode.sweep_speed();
+        line.direction = 1.0;
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            *mesh = Rectangle::new(2.0, range).into();
+        }
+    }
+}
+
 pub fn line_spin_system(
     time: Res<Time>,
-    mut line: Query<(&Line, &mut Transform)>,
-    mut sonar: Query<(&Sonar, &Transform), Without<Line>>,
+    mut line: Query<(&mut Line, &mut Transform, &mut Visibility)>,
+    sonar: Query<(&Sonar, &Transform), Without<Line>>,
+    player: Query<&Transform, (With<Player>, Without<Line>)>,
+    compartments: Query<&Compartments>,
 ) {
-    let (line, mut transform) = line.single_mut();
-    {
-        let old_w = transform.rotation.w;
-        transform.rotate_z(-1. * line.rotation_speed * time.delta_seconds());
-        if transform.rotation.w > old_w {
-            transform.rotation.w *= -1.0;
-            transform.rotation.z *= -1.0;
+    let (mut line, mut transform, mut visibility) = line.single_mut();
+    line.swept = 0.0;
+    let operational = compartments.get_single().map_or(true, |c| c.operational(Subsystem::Sonar));
+    *visibility = if operational { Visibility::Inherited } else { Visibility::Hidden };
+    if !operational {
+        return;
+    }
+    let (sonar, sonar_transform) = sonar.single();
+    let step = line.rotation_speed * time.delta_seconds();
+    let from = line.angle;
+    let mut to = from + line.direction * step;
+    if sonar.mode == SonarMode::Sector {
+        // Bounce between the edges of the arc, which turns with the boat
+        let bow = player.get_single().map_or(0.0, |player| -heading((player.rotation * Vec3::Y).xy()));
+        let half_width = SECTOR_WIDTH.to_radians() / 2.0;
+        let offset = wrap_angle(to - bow);
+        if offset.abs() >= half_width {
+            to = bow + offset.clamp(-half_width, half_width);
+            line.direction = -offset.signum();
         }
     }
-    {
-        let (sonar, sonar_transform) = sonar.single_mut();
-        let radius = sonar.radius / 2.0;
-        let (_, radian) = transform.rotation.to_axis_angle();
-        let x = radian.sin() * radius + sonar_transform.translation.x;
-        let y = radian.cos() * radius + sonar_transform.translation.y;
-        *transform = transform.with_translation(Vec3::new(x, y, transform.translation.z));
+    line.angle = to.rem_euclid(std::f32::consts::TAU);
+    // Jumping into a sector from outside it isn't a sweep, so it detects nothing
+    let swept = wrap_angle(to - from);
+    line.swept = if swept.abs() <= step.abs() + 1e-4 { swept } else { 0.0 };
+    // The mesh is centered, so it sits halfway out to reach from the center to the edge of the range
+    let reach = sonar.range() / 2.0;
+    let x = line.angle.sin() * reach + sonar_transform.translation.x;
+    let y = line.angle.cos() * reach + sonar_transform.translation.y;
+    *transform = Transform {
+        translation: Vec3::new(x, y, transform.translation.z),
+        rotation: Quat::from_rotation_z(-line.angle),
+        ..*transform
+    };
+}
+
+/// Folds an angle into -PI..PI
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI
+}
+
+/// Whether a contact at `offset` from the scope's center lies in the sector a sweep covered, `swept` radians
+/// clockwise from north ending at `angle`, within `range`.
+/// The sector is open at its start so a contact right on the boundary is found by one tick, not two.
+pub fn in_sweep(offset: Vec2, angle: f32, swept: f32, range: f32) -> bool {
+    if swept == 0.0 || offset.length() > range {
+        return false;
     }
+    let bearing = offset.x.atan2(offset.y);
+    let start = angle - swept;
+    let into = (if swept > 0.0 { bearing - start } else { start - bearing }).rem_euclid(std::f32::consts::TAU);
+    into > 0.0 && into <= swept.abs()
 }
 
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub fn ping_system(
-    mut event_reader: EventReader<Collision>,
+    mut commands: Commands,
     line_query: Query<&Line>,
-    mut pingable_query: Query<&mut Pingable, With<Sprite>>,
+    sonar_query: Query<(&Sonar, &Transform)>,
+    mut pingable_query: Query<(Entity, &mut Pingable, &Transform, Option<&Signature>), (With<Sprite>, Without<Echo>)>,
+    compartments: Query<&Compartments>,
+    noise: Res<SonarNoise>,
+    mut rng: ResMut<GameRng>,
+    mut ping_events: EventWriter<PingEvent>,
 ) {
-    for event in event_reader.read() {
-        let (entity_a, entity_b) = (event.entity_a, event.entity_b);
-        let items = if line_query.get(entity_a).is_ok() {
-            Some((entity_a, entity_b))
-        } else if line_query.get(entity_b).is_ok() {
-            Some((entity_b, entity_a))
-        } else {
-            None
-        };
-        if let Some((l, p)) = items {
-            let Ok(_) = line_query.get(l) else { return; };
-            let Ok(mut pingable) = pingable_query.get_mut(p) else { return; };
-
-            let keep = pingable.keep;
-            pingable.timer.set_duration(keep);
-            pingable.timer.reset();
+    if !compartments.get_single().map_or(true, |c| c.operational(Subsystem::Sonar)) {
+        return;
+    }
+    let (Ok(line), Ok((sonar, sonar_transform))) = (line_query.get_single(), sonar_query.get_single()) else { return; };
+    let center = sonar_transform.translation.xy();
+    let range = sonar.range();
+    for (entity, mut pingable, transform, signature) in pingable_query.iter_mut() {
+        let offset = transform.translation.xy() - center;
+        if !in_sweep(offset, line.angle, line.swept, range) {
+            continue;
         }
+        if !rng.gen_bool(noise.detection_chance(offset.length() / range) as f64) {
+            continue;
+        }
+        let keep = pingable.keep;
+        if pingable.timer.duration() != keep || pingable.timer.finished() {
+            let error = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU)) * rng.gen::<f32>() * noise.jitter * offset.length();
+            let position = transform.translation + error.extend(0.0);
+            if signature.is_some() {
+                spawn_echo(&mut commands, position);
+            }
+            ping_events.send(PingEvent { entity, position, signature: signature.copied() });
+        }
+        pingable.timer.set_duration(keep);
+        pingable.timer.reset();
     }
+
+    let chance = (noise.false_contacts * line.swept.abs() / std::f32::consts::TAU).clamp(0.0, 1.0);
+    if rng.gen_bool(chance as f64) {
+        let angle = line.angle - line.swept * rng.gen::<f32>();
+        // Square root spreads the blips evenly over the area rather than bunching them at the center
+        let distance = range * rng.gen::<f32>().sqrt();
+        let position = (center + Vec2::new(angle.sin(), angle.cos()) * distance).extend(0.0);
+        let entity = spawn_echo(&mut commands, position);
+        commands.entity(entity).insert(FalseContact);
+        ping_events.send(PingEvent { entity, position, signature: None });
+    }
+}
+
+fn spawn_echo(
+    commands: &mut Commands,
+    position: Vec3,
+) -> Entity {
+    let pingable = Pingable::default().pinged();
+    let lifetime = pingable.keep + pingable.fade_away;
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::GREEN,
+                custom_size: Some(Vec2::splat(6.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        pingable,
+        Echo {
+            lifetime: Timer::new(lifetime, TimerMode::Once),
+        },
+        GameDespawnable,
+    )).id()
 }
 
 pub fn fade_away_system(
     time: Res<Time>,
-    mut query: Query<(&mut Pingable, &mut Sprite)>,
+    mut query: Query<(&mut Pingable, &mut Sprite, Has<Signature>)>,
+    mut diagnostics: Diagnostics,
 ) {
-    for (mut pingable, mut sprite) in query.iter_mut() {
+    let start = Instant::now();
+    for (mut pingable, mut sprite, heard) in query.iter_mut() {
         let color = if pingable.timer.finished() {
             if pingable.timer.duration() == pingable.keep {
                 let fade_away = pingable.fade_away;
@@ -187,6 +476,20 @@ pub fn fade_away_system(
             }
         };
         pingable.timer.tick(Duration::from_secs_f32(time.delta_seconds()));
-        sprite.color = color;
+        // Only ever seen through its echoes
+        sprite.color = if heard { color.with_a(0.0) } else { color };
+    }
+    diagnostics.add_measurement(&FADE_AWAY_SYSTEM_TIME, || start.elapsed().as_secs_f64() * 1000.0);
+}
+
+fn echo_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Echo)>,
+) {
+    for (entity, mut echo) in query.iter_mut() {
+        if echo.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
     }
 }
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use bevy::audio::Volume;
+use crate::gamestate::GameState;
+use crate::gamestate::despawn_system;
+use crate::gamestate::GameDespawnable;
+use crate::sonar::Sonar;
+use crate::sonar::PingEvent;
+use crate::torpedo::TorpedoLaunchEvent;
+use crate::torpedo::EnemyDamageEvent;
+use crate::player::PlayerHitEvent;
+use crate::loading::GameAssets;
+use crate::loading::RequestAssets;
+
+pub struct SoundPlugin;
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AudioSettings::default())
+            .add_systems(Startup, spawn_listener)
+            .add_systems(OnEnter(GameState::Loading), spawn_ambience.after(RequestAssets))
+            .add_systems(Update, ping_sound_system.run_if(in_state(GameState::Game)))
+            .add_systems(Update, launch_sound_system.run_if(in_state(GameState::Game)))
+            .add_systems(Update, explosion_sound_system.run_if(in_state(GameState::Game)))
+            .add_systems(Update, music_volume_system.run_if(resource_changed::<AudioSettings>))
+            .add_systems(OnEnter(GameState::Pause), pause_ambience)
+            // Not on leaving Pause, which also leads to the settings screen mid-game
+            .add_systems(OnEnter(GameState::Game), resume_ambience)
+            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>);
+    }
+}
+
+#[derive(Resource)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub sfx: f32,
+    pub music: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> AudioSettings {
+        AudioSettings {
+            master: 1.0,
+            sfx: 0.8,
+            music: 0.5,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn sfx_volume(&self) -> f32 {
+        self.master * self.sfx
+    }
+
+    pub fn music_volume(&self) -> f32 {
+        self.master * self.music
+    }
+}
+
+#[derive(Component)]
+struct Ambience;
+
+// Sounds are panned in their own "audio space": the listener sits at the origin and
+// emitters are placed on a unit circle around it, following the contact's bearing from
+// the sonar centre. Distance doesn't matter, only direction.
+const EAR_GAP: f32 = 1.0;
+const EMITTER_DISTANCE: f32 = 1.0;
+
+fn spawn_listener(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        TransformBundle::default(),
+        SpatialListener::new(EAR_GAP),
+    ));
+}
+
+fn spawn_ambience(
+    mut commands: Commands,
+    sounds: Res<GameAssets>,
+    settings: Res<AudioSettings>,
+) {
+    commands.spawn((
+        AudioBundle {
+            source: sounds.ambience.clone(),
+            settings: PlaybackSettings::LOOP.with_volume(Volume::new(settings.music_volume())),
+        },
+        Ambience,
+        GameDespawnable,
+    ));
+}
+
+fn play_at_bearing(
+    commands: &mut Commands,
+    source: Handle<AudioSource>,
+    volume: f32,
+    position: Vec3,
+    sonar_center: Vec3,
+) {
+    let bearing = (position - sonar_center).xy().normalize_or_zero();
+    commands.spawn((
+        AudioBundle {
+            source,
+            settings: PlaybackSettings::DESPAWN
+                .with_volume(Volume::new(volume))
+                .with_spatial(bearing != Vec2::ZERO),
+        },
+        TransformBundle::from_transform(Transform::from_translation((bearing * EMITTER_DISTANCE).extend(0.0))),
+    ));
+}
+
+fn ping_sound_system(
+    mut commands: Commands,
+    sounds: Res<GameAssets>,
+    settings: Res<AudioSettings>,
+    mut ping_events: EventReader<PingEvent>,
+    sonar_query: Query<&Transform, With<Sonar>>,
+) {
+    let Ok(sonar_transform) = sonar_query.get_single() else { return; };
+    for event in ping_events.read() {
+        play_at_bearing(&mut commands, sounds.ping.clone(), settings.sfx_volume(), event.position, sonar_transform.translation);
+    }
+}
+
+fn launch_sound_system(
+    mut commands: Commands,
+    sounds: Res<GameAssets>,
+    settings: Res<AudioSettings>,
+    mut launch_events: EventReader<TorpedoLaunchEvent>,
+    sonar_query: Query<&Transform, With<Sonar>>,
+) {
+    let Ok(sonar_transform) = sonar_query.get_single() else { return; };
+    for event in launch_events.read() {
+        play_at_bearing(&mut commands, sounds.launch.clone(), settings.sfx_volume(), event.position, sonar_transform.translation);
+    }
+}
+
+fn explosion_sound_system(
+    mut commands: Commands,
+    sounds: Res<GameAssets>,
+    settings: Res<AudioSettings>,
+    mut player_hits: EventReader<PlayerHitEvent>,
+    mut enemy_damage_events: EventReader<EnemyDamageEvent>,
+    transform_query: Query<&Transform>,
+    sonar_query: Query<&Transform, With<Sonar>>,
+) {
+    let Ok(sonar_transform) = sonar_query.get_single() else { return; };
+    let center = sonar_transform.translation;
+    for hit in player_hits.read() {
+        let position = center + hit.direction.extend(0.0);
+        play_at_bearing(&mut commands, sounds.explosion.clone(), settings.sfx_volume(), position, center);
+    }
+    for event in enemy_damage_events.read() {
+        let position = transform_query.get(event.entity).map_or(center, |t| t.translation);
+        play_at_bearing(&mut commands, sounds.explosion.clone(), settings.sfx_volume(), position, center);
+    }
+}
+
+fn music_volume_system(
+    settings: Res<AudioSettings>,
+    ambience_query: Query<&AudioSink, With<Ambience>>,
+) {
+    for sink in ambience_query.iter() {
+        sink.set_volume(settings.music_volume());
+    }
+}
+
+fn pause_ambience(
+    ambience_query: Query<&AudioSink, With<Ambience>>,
+) {
+    for sink in ambience_query.iter() {
+        sink.pause();
+    }
+}
+
+fn resume_ambience(
+    ambience_query: Query<&AudioSink, With<Ambience>>,
+) {
+    for sink in ambience_query.iter() {
+        sink.play();
+    }
+}
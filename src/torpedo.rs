@@ -3,32 +3,66 @@ use crate::gamestate::GameState;
 use crate::gamestate::despawn_system;
 use crate::gamestate::GameDespawnable;
 use crate::player::Player;
+use crate::player::Compartments;
+use crate::player::Subsystem;
+use crate::actions::Action;
+use crate::actions::ActionState;
 use std::time::Duration;
 use std::f32::consts::PI;
 use crate::sonar::Pingable;
+use crate::sonar::Signature;
 use crate::hitbox::Hitbox;
 use crate::hitbox::InvulnerableAfterSpawn;
 use crate::hitbox::Collision;
 use crate::enemy::Enemy;
 use crate::enemy::EnemyPositions;
+use crate::loading::GameAssets;
+use crate::simulation::SimulationSet;
+use crate::enemy::enemy_fire_system;
+use crate::console::ConsoleCommandsExt;
+use crate::console::ConsoleResult;
+use crate::diagnostics::MOVE_TORPEDO_SYSTEM_TIME;
+use crate::firecontrol::FiringSolution;
+use crate::tracking::Tracks;
+use crate::decoy::Decoy;
+use crate::decoy::PlayerDecoy;
+use crate::decoy::EnemyDecoy;
+use crate::decoy::DECOY_LOUDNESS;
+use crate::simulation::SimulationTick;
+use bevy::diagnostic::Diagnostics;
+use bevy::utils::Instant;
 // use bevy::ecs::query::QueryEntityError;
 
 pub struct TorpedoPlugin;
 impl Plugin for TorpedoPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<FireRegularTorpedo>()
+            .add_event::<FireGuidedTorpedo>()
             .add_event::<PlayerDamageEvent>()
             .add_event::<EnemyDamageEvent>()
-            .add_systems(Update, player_shoot_torpedo_system.run_if(in_state(GameState::Game)))
-            .add_systems(Update, shoot_torpedo_event_system.run_if(in_state(GameState::Game)))
-            .add_systems(Update, collide_system.run_if(in_state(GameState::Game)))
-            .add_systems(Update, move_torpedo_system.run_if(in_state(GameState::Game)))
-            .add_systems(Update, move_counter_torpedo_system.run_if(in_state(GameState::Game)))
+            .add_event::<TorpedoLaunchEvent>()
+            .add_systems(FixedUpdate, player_shoot_torpedo_system.in_set(SimulationSet::Ai).run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, shoot_torpedo_event_system.in_set(SimulationSet::Ai).after(enemy_fire_system).run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, move_torpedo_system.in_set(SimulationSet::Movement).run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, move_counter_torpedo_system.in_set(SimulationSet::Movement).run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, collide_system.in_set(SimulationSet::Damage).in_set(TorpedoCollisions).run_if(in_state(GameState::Game)))
             .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>)
-            .insert_resource(TorpedoCooldown(Timer::new(Duration::from_secs(2), TimerMode::Once)));
+            .add_systems(OnEnter(GameState::Loading), reset_cooldown)
+            .add_console_command("ammo", "", "Reload the tubes now and repair them if damaged", ammo_command)
+            .insert_resource(TorpedoCooldown::default());
     }
 }
 
+pub const PLAYER_TORPEDO_SPEED: f32 = 35.0 / 2.0;
+/// How far a guided torpedo's seeker hears
+pub const GUIDED_RANGE: f32 = 200.0;
+/// How far a counter torpedo looks for a torpedo to intercept
+pub const COUNTER_RANGE: f32 = 80.0;
+
+/// Turns torpedo collisions into damage events, systems applying damage run after it
+#[derive(SystemSet, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct TorpedoCollisions;
+
 #[derive(Component)]
 pub struct Torpedo {
     movement_speed: f32,
@@ -47,22 +81,45 @@ pub struct CounterTorpedo;
 #[derive(Component)]
 pub struct PlayerTorpedo;
 
+/// Sonar track a guided torpedo was launched at, it steers for the track until its own seeker takes over
+#[derive(Component)]
+pub struct TrackTarget(pub u32);
+
 #[derive(Component)]
 pub struct EnemyTorpedo;
 
 #[derive(Resource, Deref, DerefMut)]
 pub struct TorpedoCooldown(Timer);
 
+impl Default for TorpedoCooldown {
+    fn default() -> TorpedoCooldown {
+        TorpedoCooldown(Timer::new(Duration::from_secs(2), TimerMode::Once))
+    }
+}
+
 #[derive(Event)]
 pub struct FireRegularTorpedo {
     pub from: Vec2,
     pub towards: Vec2,
 }
 
+/// An enemy launching a guided torpedo, which homes on the player or the player's decoys
+#[derive(Event)]
+pub struct FireGuidedTorpedo {
+    pub from: Vec2,
+    pub towards: Vec2,
+}
+
+#[derive(Event)]
+pub struct TorpedoLaunchEvent {
+    pub position: Vec3,
+}
+
 #[derive(Event)]
 pub struct PlayerDamageEvent {
     pub entity: Entity,
     pub damage: i32,
+    pub source: Vec3,
 }
 
 #[derive(Event)]
@@ -71,32 +128,62 @@ pub struct EnemyDamageEvent {
     pub damage: i32,
 }
 
+// Every game starts with the tubes reloading, whatever state the last game left them in
+fn reset_cooldown(
+    mut cooldown_timer: ResMut<TorpedoCooldown>,
+) {
+    *cooldown_timer = TorpedoCooldown::default();
+}
+
+// Torpedoes are unlimited, so the only thing holding fire back is the reload and a wrecked tube compartment
+fn ammo_command(
+    In(_): In<Vec<String>>,
+    mut cooldown_timer: ResMut<TorpedoCooldown>,
+    mut player_query: Query<&mut Compartments, With<Player>>,
+) -> ConsoleResult {
+    let duration = cooldown_timer.duration();
+    cooldown_timer.set_elapsed(duration);
+    for mut compartments in player_query.iter_mut() {
+        compartments.tubes.health = compartments.tubes.max_health;
+    }
+    Ok("tubes loaded".to_string())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn player_shoot_torpedo_system(
     time: Res<Time>,
-    asset_server: Res<AssetServer>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    assets: Res<GameAssets>,
+    action_state: Res<ActionState>,
     mut commands: Commands,
-    mut player_query: Query<(&Player, &mut Transform)>,
+    mut player_query: Query<(&Player, &Compartments, &mut Transform)>,
     mut cooldown_timer: ResMut<TorpedoCooldown>,
+    mut launch_events: EventWriter<TorpedoLaunchEvent>,
+    solution: Res<FiringSolution>,
+    tracks: Res<Tracks>,
 ) {
     cooldown_timer.tick(Duration::from_secs_f32(time.delta_seconds()));
     if !cooldown_timer.finished()  {
         return
     }
-    let (_, player_transform) = player_query.single_mut();
-    if keyboard_input.pressed(KeyCode::Space) {
+    let (_, compartments, player_transform) = player_query.single_mut();
+    if !compartments.operational(Subsystem::Tubes) {
+        return
+    }
+    // With a firing solution, tubes are trained on the intercept bearing instead of the bow
+    let launch_rotation = solution.map_or(player_transform.rotation, |solution| Quat::from_rotation_z(solution.bearing));
+    if action_state.pressed(Action::FireRegular) {
         commands.spawn((
             SpriteBundle {
-                texture: asset_server.load("../assets/torpedo-comum.png"),
+                texture: assets.regular_torpedo.clone(),
                 transform: Transform {
                     translation: player_transform.translation,
-                    rotation: player_transform.rotation,
+                    rotation: launch_rotation,
                     scale: Vec3::splat(0.2),
                 },
                 ..default()
             },
             Torpedo {
-                movement_speed: (35.0/2.0),
+                movement_speed: PLAYER_TORPEDO_SPEED,
                 damage: 1,
             },
             RegularTorpedo,
@@ -106,21 +193,22 @@ pub fn player_shoot_torpedo_system(
             Pingable::default().pinged(),
             GameDespawnable,
         ));
+        launch_events.send(TorpedoLaunchEvent { position: player_transform.translation });
         cooldown_timer.reset();
     }
-    if keyboard_input.pressed(KeyCode::ShiftLeft) {
-        commands.spawn((
+    if action_state.pressed(Action::FireGuided) {
+        let mut torpedo = commands.spawn((
             SpriteBundle {
-                texture: asset_server.load("../assets/torpedo-teleguiado.png"),
+                texture: assets.guided_torpedo.clone(),
                 transform: Transform {
                     translation: player_transform.translation,
-                    rotation: player_transform.rotation,
+                    rotation: launch_rotation,
                     scale: Vec3::splat(0.2),
                 },
                 ..default()
             },
             Torpedo {
-                movement_speed: (35.0/2.0),
+                movement_speed: PLAYER_TORPEDO_SPEED,
                 damage: 1,
             },
             GuidedTorpedo,
@@ -130,13 +218,19 @@ pub fn player_shoot_torpedo_system(
             Pingable::default().pinged(),
             GameDespawnable,
         ));
+        // A contact already known to be harmless isn't worth a torpedo's attention
+        let target = solution.0.filter(|solution| tracks.get(solution.track).is_some_and(|track| track.classification.is_target()));
+        if let Some(solution) = target {
+            torpedo.insert(TrackTarget(solution.track));
+        }
+        launch_events.send(TorpedoLaunchEvent { position: player_transform.translation });
         cooldown_timer.reset();
     }
-    if keyboard_input.pressed(KeyCode::ControlLeft) {
-        for i in vec![0.5, 1.5] {
+    if action_state.pressed(Action::FireCounter) {
+        for i in [0.5, 1.5] {
             commands.spawn((
                 SpriteBundle {
-                    texture: asset_server.load("../assets/torpedo-contramedida.png"),
+                    texture: assets.counter_torpedo.clone(),
                     transform: Transform {
                         translation: player_transform.translation,
                         rotation: player_transform.rotation * Quat::from_rotation_z(PI * i),
@@ -156,26 +250,31 @@ pub fn player_shoot_torpedo_system(
                 GameDespawnable,
             ));
         }
+        launch_events.send(TorpedoLaunchEvent { position: player_transform.translation });
         cooldown_timer.reset();
     }
 }
 
 fn shoot_torpedo_event_system(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
     mut regular_ev_reader: EventReader<FireRegularTorpedo>,
+    mut guided_ev_reader: EventReader<FireGuidedTorpedo>,
+    mut launch_events: EventWriter<TorpedoLaunchEvent>,
 ) {
-    for (event, _) in regular_ev_reader.read_with_id() {
-        let angle = if event.towards.x < 0.0 {
-            event.towards.extend(0.0).angle_between(Vec3::Y)
+    let regular = regular_ev_reader.read().map(|event| (event.from, event.towards, false));
+    let guided = guided_ev_reader.read().map(|event| (event.from, event.towards, true));
+    for (from, towards, guided) in regular.chain(guided) {
+        let angle = if towards.x < 0.0 {
+            towards.extend(0.0).angle_between(Vec3::Y)
         } else {
-            (event.towards * -1.0).extend(0.0).angle_between(Vec3::Y) + PI
+            (towards * -1.0).extend(0.0).angle_between(Vec3::Y) + PI
         };
-        commands.spawn((
+        let mut torpedo = commands.spawn((
             SpriteBundle {
-                texture: asset_server.load("../assets/torpedo-comum.png"),
+                texture: if guided { assets.guided_torpedo.clone() } else { assets.regular_torpedo.clone() },
                 transform: Transform {
-                    translation: event.from.extend(0.0),
+                    translation: from.extend(0.0),
                     rotation: Quat::from_rotation_z(angle),
                     scale: Vec3::splat(0.2),
                 },
@@ -185,45 +284,54 @@ fn shoot_torpedo_event_system(
                 movement_speed: (35.0/2.0),
                 damage: 1,
             },
-            RegularTorpedo,
             EnemyTorpedo,
-            Hitbox::new(10.0, 50.0),
             InvulnerableAfterSpawn,
             Pingable::default(),
+            Signature::TORPEDO,
             GameDespawnable,
         ));
+        if guided {
+            torpedo.insert((GuidedTorpedo, Hitbox::new(15.0, 60.0)));
+        } else {
+            torpedo.insert((RegularTorpedo, Hitbox::new(10.0, 50.0)));
+        }
+        launch_events.send(TorpedoLaunchEvent { position: from.extend(0.0) });
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn collide_system(
     mut commands: Commands,
     mut event_reader: EventReader<Collision>,
     mut damage_event_writer: EventWriter<PlayerDamageEvent>,
     mut damage_event_writer2: EventWriter<EnemyDamageEvent>,
-    torpedo_query: Query<(Entity, &Hitbox, &Torpedo), With<Torpedo>>,
+    torpedo_query: Query<(Entity, &Hitbox, &Torpedo, &Transform), With<Torpedo>>,
     enemy_query: Query<Entity, With<Enemy>>,
     //enemy_torpedo_query: Query<Entity, With<EnemyTorpedo>>, // Query para identificar torpedos do inimigo
     player_query: Query<Entity, With<Player>>,
     player_torpedo_query: Query<Entity, With<PlayerTorpedo>>, // Query para identificar torpedos do jogador
+    enemy_torpedo_query: Query<(), With<EnemyTorpedo>>,
+    player_decoy_query: Query<(), With<PlayerDecoy>>,
+    enemy_decoy_query: Query<(), With<EnemyDecoy>>,
 ) {
     for event in event_reader.read() {
         let entity_a = event.entity_a;
         let entity_b = event.entity_b;
 
         // Verifica se o torpedo colidiu com o inimigo e se é um torpedo do jogador
-        let torpedo_enemy_collision = if torpedo_query.get_component::<Hitbox>(entity_a).is_ok() && enemy_query.get(entity_b).is_ok() {
+        let torpedo_enemy_collision = if torpedo_query.get(entity_a).is_ok() && enemy_query.get(entity_b).is_ok() {
             player_torpedo_query.get(entity_a).ok().map(|_| entity_a)
-        } else if torpedo_query.get_component::<Hitbox>(entity_b).is_ok() && enemy_query.get(entity_a).is_ok() {
+        } else if torpedo_query.get(entity_b).is_ok() && enemy_query.get(entity_a).is_ok() {
             player_torpedo_query.get(entity_b).ok().map(|_| entity_b)
         } else {
             None
         };
 
         // Verifica se o torpedo colidiu com o jogador (mantém a lógica anterior)
-        let torpedo_player_collision = if torpedo_query.get_component::<Hitbox>(entity_a).is_ok() && player_query.get(entity_b).is_ok() {
+        let torpedo_player_collision = if torpedo_query.get(entity_a).is_ok() && player_query.get(entity_b).is_ok() {
             Some(entity_a)
             //enemy_torpedo_query.get(entity_a).ok().map(|_| entity_a)
-        } else if torpedo_query.get_component::<Hitbox>(entity_b).is_ok() && player_query.get(entity_a).is_ok() {
+        } else if torpedo_query.get(entity_b).is_ok() && player_query.get(entity_a).is_ok() {
             Some(entity_b)
             //enemy_torpedo_query.get(entity_b).ok().map(|_| entity_a)
         } else {
@@ -235,7 +343,7 @@ fn collide_system(
             // Se o torpedo colidiu com um inimigo, apenas despawne o torpedo
             let enemy_hit = if enemy_query.get(entity_a).is_ok() { entity_a } else { entity_b };
 
-            if let Ok((_, _,  torpedo_component)) = torpedo_query.get(torpedo) {
+            if let Ok((_, _,  torpedo_component, _)) = torpedo_query.get(torpedo) {
                 println!("Torpedo colidiu com inimigo, despawnando torpedo");
                 damage_event_writer2.send(EnemyDamageEvent {
                     entity: enemy_hit,
@@ -247,24 +355,88 @@ fn collide_system(
 
             let player_hit = if player_query.get(entity_a).is_ok() { entity_a } else { entity_b };
             // Se o torpedo colidiu com o jogador, emita o evento de dano antes de despawnar o torpedo
-            if let Ok((_, _,  torpedo_component)) = torpedo_query.get(torpedo) {
+            if let Ok((_, _,  torpedo_component, torpedo_transform)) = torpedo_query.get(torpedo) {
                 damage_event_writer.send(PlayerDamageEvent {
                     entity: player_hit,
                     damage: torpedo_component.damage,
+                    source: torpedo_transform.translation,
                 });
             }
             println!("Torpedo colidiu com o jogador, despawnando torpedo");
             commands.entity(torpedo).despawn();
+        } else {
+            // A torpedo that runs into the other side's decoy takes it out along with itself
+            let seduced = [(entity_a, entity_b), (entity_b, entity_a)].into_iter().find(|(torpedo, decoy)| {
+                (player_torpedo_query.contains(*torpedo) && enemy_decoy_query.contains(*decoy))
+                    || (enemy_torpedo_query.contains(*torpedo) && player_decoy_query.contains(*decoy))
+            });
+            if let Some((torpedo, decoy)) = seduced {
+                commands.entity(torpedo).despawn();
+                commands.entity(decoy).despawn();
+            }
         }
     }
 }
 
+/// Something a guided torpedo's seeker can hear
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Lure {
+    pub position: Vec3,
+    /// 1.0 for a boat, `DECOY_LOUDNESS` for a decoy
+    pub loudness: f32,
+}
+
+impl Lure {
+    /// What one side's seekers hear: the other side's boats and decoys
+    pub fn gather(boats: impl IntoIterator<Item = Vec3>, decoys: impl IntoIterator<Item = Vec3>) -> Vec<Lure> {
+        boats.into_iter().map(|position| Lure { position, loudness: 1.0 })
+            .chain(decoys.into_iter().map(|position| Lure { position, loudness: DECOY_LOUDNESS }))
+            .collect()
+    }
+}
+
+/// What a guided torpedo at `from` steers for: the lure within `GUIDED_RANGE` that sounds loudest from there.
+/// Loudness fades with distance, so a decoy seduces a torpedo away from a boat unless the boat is much closer.
+pub fn guided_target(lures: &[Lure], from: Vec3) -> Option<Vec3> {
+    let heard = |lure: &Lure| lure.loudness / lure.position.distance(from).max(1.0);
+    lures.iter()
+        .filter(|lure| lure.position.distance(from) <= GUIDED_RANGE)
+        .max_by(|a, b| heard(a).total_cmp(&heard(b)))
+        .map(|lure| lure.position)
+}
+
+/// Where a guided torpedo at `from` steers: where sonar expects the track it was launched at,
+/// until that is close enough for its own seeker to take over or is classified as something not worth hitting
+pub fn guided_aim(track_target: Option<&TrackTarget>, tracks: &Tracks, tick: u32, lures: &[Lure], from: Vec3) -> Option<Vec3> {
+    track_target
+        .and_then(|target| tracks.get(target.0))
+        .filter(|track| track.classification.is_target())
+        .map(|track| track.estimated_position(tick).extend(0.0))
+        .filter(|position| position.distance(from) > GUIDED_RANGE)
+        .or_else(|| guided_target(lures, from))
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn move_torpedo_system(
     time: Res<Time>,
-    mut query: Query<(&mut Transform, &Torpedo, Option<&RegularTorpedo>, Option<&GuidedTorpedo>, Option<&CounterTorpedo>)>,
+    mut query: Query<(&mut Transform, &Torpedo, Option<&RegularTorpedo>, Option<&GuidedTorpedo>, Option<&CounterTorpedo>, Option<&TrackTarget>, Has<PlayerTorpedo>)>,
     enemy_positions: Res<EnemyPositions>,
+    player_query: Query<&Transform, (With<Player>, Without<Torpedo>)>,
+    decoy_query: Query<(&Transform, Has<PlayerDecoy>), (With<Decoy>, Without<Torpedo>)>,
+    tracks: Res<Tracks>,
+    tick: Res<SimulationTick>,
+    mut diagnostics: Diagnostics,
 ) {
-    for (mut torpedo_transform, torpedo, regular, guided, _counter) in query.iter_mut() {
+    let start = Instant::now();
+    let player_lures = Lure::gather(
+        enemy_positions.positions.iter().copied(),
+        decoy_query.iter().filter(|(_, player)| !player).map(|(transform, _)| transform.translation),
+    );
+    let enemy_lures = Lure::gather(
+        player_query.iter().map(|transform| transform.translation),
+        decoy_query.iter().filter(|(_, player)| *player).map(|(transform, _)| transform.translation),
+    );
+    for (mut torpedo_transform, torpedo, regular, guided, _counter, track_target, player_torpedo) in query.iter_mut() {
         if regular.is_some()
         {
             let up = torpedo_transform.up();
@@ -272,17 +444,9 @@ fn move_torpedo_system(
         }
         else if guided.is_some()
         {
-            let in_range_enemies: Vec<&Vec3> = enemy_positions.positions.iter().filter(|&enemy_pos| {
-                let distance = enemy_pos.distance(torpedo_transform.translation);
-                distance <= 200.0 // Distância máxima
-            }).collect();
-
-            if let Some(closest_enemy_position) = in_range_enemies.iter().min_by(|a, b| {
-                let distance_a = a.distance(torpedo_transform.translation);
-                let distance_b = b.distance(torpedo_transform.translation);
-                distance_a.partial_cmp(&distance_b).unwrap_or(std::cmp::Ordering::Equal)
-            }) {
-                let direction_to_enemy = (**closest_enemy_position - torpedo_transform.translation).normalize();
+            let lures = if player_torpedo { &player_lures } else { &enemy_lures };
+            if let Some(closest_enemy_position) = guided_aim(track_target, &tracks, tick.0, lures, torpedo_transform.translation) {
+                let direction_to_enemy = (closest_enemy_position - torpedo_transform.translation).normalize();
                 torpedo_transform.translation += direction_to_enemy * torpedo.movement_speed * time.delta_seconds();
                 let angle_to_enemy = Vec3::Y.angle_between(direction_to_enemy);
                 let axis_of_rotation = Vec3::Y.cross(direction_to_enemy).normalize_or_zero();
@@ -296,6 +460,7 @@ fn move_torpedo_system(
             }
         }
     }
+    diagnostics.add_measurement(&MOVE_TORPEDO_SYSTEM_TIME, || start.elapsed().as_secs_f64() * 1000.0);
 }
 
 fn move_counter_torpedo_system(
@@ -309,7 +474,7 @@ fn move_counter_torpedo_system(
             .map(|(t, _)| t.translation)
             .filter(|t| {
                 let distance = t.distance(counter_transform.translation);
-                distance <= 80.0 // Distância máxima
+                distance <= COUNTER_RANGE
             })
             .collect();
 
@@ -0,0 +1,367 @@
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+use bevy::sprite::Mesh2dHandle;
+use crate::gamestate::GameState;
+use crate::gamestate::despawn_system;
+use crate::gamestate::GameDespawnable;
+use crate::actions::Action;
+use crate::actions::ActionState;
+use crate::sonar::PingEvent;
+use crate::sonar::Signature;
+use crate::sonar::ping_system;
+use crate::torpedo::PlayerTorpedo;
+use crate::decoy::PlayerDecoy;
+use crate::simulation::SimulationSet;
+use crate::simulation::SimulationTick;
+use crate::simulation::TICK_RATE;
+use crate::simulation::heading;
+
+pub struct TrackingPlugin;
+impl Plugin for TrackingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tracks>()
+            .add_systems(Startup, setup_marker_meshes)
+            .add_systems(OnEnter(GameState::Loading), reset_tracks)
+            .add_systems(FixedUpdate, (track_contacts_system, decay_tracks_system, track_selection_system).chain().in_set(SimulationSet::Damage).after(ping_system).run_if(in_state(GameState::Game)))
+            .add_systems(Update, track_marker_system.run_if(in_state(GameState::Game)))
+            .add_systems(OnEnter(GameState::Menu), despawn_system::<GameDespawnable>);
+    }
+}
+
+/// Seconds a track survives without being detected again
+const TRACK_MEMORY: f32 = 10.0;
+/// How far from where a track is expected a detection without a known source may be and still update it
+const TRACK_GATE: f32 = 50.0;
+/// Weight of the newest measurement in the velocity estimate
+const VELOCITY_SMOOTHING: f32 = 0.5;
+/// Seconds of travel the course leader on a marker shows
+const LEADER_SECONDS: f32 = 4.0;
+/// Seconds on track it takes to classify a contact with a signature of strength 1.0
+pub const CLASSIFY_SECONDS: f32 = 6.0;
+
+/// What sonar has made a track out to be
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum Classification {
+    #[default]
+    Unknown,
+    Biologic,
+    EnemySub,
+    Torpedo,
+    Decoy,
+}
+
+impl Classification {
+    pub fn label(self) -> &'static str {
+        match self {
+            Classification::Unknown => "UNK",
+            Classification::Biologic => "BIO",
+            Classification::EnemySub => "SUB",
+            Classification::Torpedo => "TORP",
+            Classification::Decoy => "DECOY",
+        }
+    }
+
+    /// Whether a guided torpedo may be sent after a contact classified as this
+    pub fn is_target(self) -> bool {
+        matches!(self, Classification::Unknown | Classification::EnemySub)
+    }
+}
+
+/// What sonar knows about one contact, kept after its echo fades
+#[derive(Clone, PartialEq, Debug)]
+pub struct Track {
+    pub id: u32,
+    /// What the last detection came from, if known
+    pub entity: Option<Entity>,
+    /// Where the contact was last detected
+    pub position: Vec2,
+    /// Estimated from consecutive detections, in units per second
+    pub velocity: Vec2,
+    /// 1.0 right after a detection, falls to 0.0 over `TRACK_MEMORY`, then the track is dropped
+    pub confidence: f32,
+    pub detections: u32,
+    pub classification: Classification,
+    /// The last signature heard from the contact
+    signature: Option<Signature>,
+    first_seen: u32,
+    last_seen: u32,
+}
+
+impl Track {
+    /// Direction of travel in radians, counter-clockwise from up like the sprites' rotation
+    pub fn course(&self) -> f32 {
+        heading(self.velocity)
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.velocity.length()
+    }
+
+    /// Seconds since the last detection
+    pub fn age(&self, tick: u32) -> f32 {
+        tick.saturating_sub(self.last_seen) as f32 / TICK_RATE as f32
+    }
+
+    /// Seconds since the track was started
+    pub fn time_on_track(&self, tick: u32) -> f32 {
+        tick.saturating_sub(self.first_seen) as f32 / TICK_RATE as f32
+    }
+
+    /// Settles the classification once the contact has been held long enough for its signature to tell
+    fn classify(&mut self, tick: u32) {
+        let Some(signature) = self.signature else { return; };
+        if self.detections >= 2 && self.time_on_track(tick) * signature.strength >= CLASSIFY_SECONDS {
+            self.classification = signature.class;
+        }
+    }
+
+    /// Dead-reckoned position now, assuming the contact kept its course and speed
+    pub fn estimated_position(&self, tick: u32) -> Vec2 {
+        self.position + self.velocity * self.age(tick)
+    }
+}
+
+/// Every live track, plus the one the player has selected and the one designated for fire control
+#[derive(Resource, Default)]
+pub struct Tracks {
+    tracks: Vec<Track>,
+    next_id: u32,
+    selected: Option<u32>,
+    designated: Option<u32>,
+}
+
+impl Tracks {
+    pub fn iter(&self) -> impl Iterator<Item = &Track> {
+        self.tracks.iter()
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Track> {
+        self.tracks.iter().find(|track| track.id == id)
+    }
+
+    pub fn selected(&self) -> Option<&Track> {
+        self.selected.and_then(|id| self.get(id))
+    }
+
+    pub fn designated(&self) -> Option<&Track> {
+        self.designated.and_then(|id| self.get(id))
+    }
+
+    /// Moves the selection to the next track by ID, wrapping around
+    pub fn cycle(&mut self) {
+        let current = self.selected.unwrap_or(0);
+        let next = self.tracks.iter().map(|track| track.id).filter(|id| *id > current).min()
+            .or_else(|| self.tracks.iter().map(|track| track.id).min());
+        self.selected = next;
+    }
+
+    /// Designates the selected track, or clears the designation if it already is
+    pub fn designate(&mut self) {
+        self.designated = if self.designated == self.selected { None } else { self.selected };
+    }
+
+    /// Folds a detection into the track it belongs to, or starts a new one
+    pub fn detect(&mut self, entity: Option<Entity>, position: Vec2, signature: Option<Signature>, tick: u32) {
+        let same_source = entity.and_then(|entity| self.tracks.iter().position(|track| track.entity == Some(entity)));
+        let nearby = || self.tracks.iter()
+            .enumerate()
+            .filter(|(_, track)| track.entity.is_none() || entity.is_none())
+            .map(|(i, track)| (i, track.estimated_position(tick).distance(position)))
+            .filter(|(_, distance)| *distance <= TRACK_GATE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i);
+        match same_source.or_else(nearby) {
+            Some(i) => {
+                let track = &mut self.tracks[i];
+                let elapsed = track.age(tick);
+                if elapsed > 0.0 {
+                    let measured = (position - track.position) / elapsed;
+                    track.velocity = if track.detections == 1 { measured } else { track.velocity.lerp(measured, VELOCITY_SMOOTHING) };
+                }
+                track.entity = entity.or(track.entity);
+                track.signature = signature.or(track.signature);
+                track.position = position;
+                track.confidence = 1.0;
+                track.detections += 1;
+                track.last_seen = tick;
+                track.classify(tick);
+            }
+            None => {
+                self.next_id += 1;
+                self.tracks.push(Track {
+                    id: self.next_id,
+                    entity,
+                    position,
+                    velocity: Vec2::ZERO,
+                    confidence: 1.0,
+                    detections: 1,
+                    classification: Classification::Unknown,
+                    signature,
+                    first_seen: tick,
+                    last_seen: tick,
+                });
+            }
+        }
+    }
+}
+
+fn reset_tracks(
+    mut tracks: ResMut<Tracks>,
+) {
+    *tracks = Tracks::default();
+}
+
+#[allow(clippy::type_complexity)]
+fn track_contacts_system(
+    mut ping_events: EventReader<PingEvent>,
+    mut tracks: ResMut<Tracks>,
+    tick: Res<SimulationTick>,
+    own_launches: Query<(), Or<(With<PlayerTorpedo>, With<PlayerDecoy>)>>,
+) {
+    for event in ping_events.read() {
+        // The boat knows where its own torpedoes and decoys are
+        if own_launches.contains(event.entity) {
+            continue;
+        }
+        tracks.detect(Some(event.entity), event.position.xy(), event.signature, tick.0);
+    }
+}
+
+fn decay_tracks_system(
+    time: Res<Time>,
+    mut tracks: ResMut<Tracks>,
+) {
+    let decay = time.delta_seconds() / TRACK_MEMORY;
+    for track in tracks.tracks.iter_mut() {
+        track.confidence -= decay;
+    }
+    tracks.tracks.retain(|track| track.confidence > 0.0);
+    if tracks.selected().is_none() {
+        tracks.selected = None;
+    }
+    if tracks.designated().is_none() {
+        tracks.designated = None;
+    }
+}
+
+// Runs on the tick so recorded input replays the same selections
+fn track_selection_system(
+    action_state: Res<ActionState>,
+    mut tracks: ResMut<Tracks>,
+) {
+    if action_state.tick_just_pressed(Action::CycleTrack) {
+        tracks.cycle();
+    }
+    if action_state.tick_just_pressed(Action::DesignateTrack) {
+        tracks.designate();
+    }
+}
+
+#[derive(Resource)]
+struct MarkerMeshes {
+    diamond: Mesh2dHandle,
+    leader: Mesh2dHandle,
+}
+
+#[derive(Component)]
+pub struct TrackMarker(pub u32);
+
+#[derive(Component)]
+struct TrackLeader;
+
+fn setup_marker_meshes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    commands.insert_resource(MarkerMeshes {
+        diamond: meshes.add(RegularPolygon::new(8.0, 4)).into(),
+        // One unit long from the origin, stretched to the contact's speed
+        leader: meshes.add(Rectangle::new(1.5, 1.0)).into(),
+    });
+}
+
+/// e.g. "T3 SUB"
+pub fn track_label(track: &Track) -> String {
+    format!("T{} {}", track.id, track.classification.label())
+}
+
+fn track_marker_system(
+    mut commands: Commands,
+    tracks: Res<Tracks>,
+    marker_meshes: Res<MarkerMeshes>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut markers: Query<(Entity, &TrackMarker, &mut Transform, &Handle<ColorMaterial>, &Children)>,
+    mut leaders: Query<&mut Transform, (With<TrackLeader>, Without<TrackMarker>)>,
+    mut labels: Query<&mut Text>,
+) {
+    for (entity, marker, mut transform, material, children) in markers.iter_mut() {
+        let Some(track) = tracks.get(marker.0) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        let color = if tracks.designated == Some(track.id) {
+            Color::RED
+        } else if tracks.selected == Some(track.id) {
+            Color::YELLOW
+        } else {
+            Color::GREEN
+        };
+        let color = color.with_a(0.3 + 0.7 * track.confidence);
+        if let Some(material) = materials.get_mut(material) {
+            material.color = color;
+        }
+        transform.translation = track.position.extend(transform.translation.z);
+        for child in children.iter() {
+            if let Ok(mut text) = labels.get_mut(*child) {
+                let label = track_label(track);
+                if text.sections[0].value != label {
+                    text.sections[0].value = label;
+                }
+                text.sections[0].style.color = color;
+            }
+            if let Ok(mut leader) = leaders.get_mut(*child) {
+                let length = track.speed() * LEADER_SECONDS;
+                leader.rotation = Quat::from_rotation_z(track.course());
+                let offset = leader.rotation * Vec3::Y * length / 2.0;
+                leader.translation = Vec3::new(offset.x, offset.y, leader.translation.z);
+                leader.scale = Vec3::new(1.0, length.max(0.001), 1.0);
+            }
+        }
+    }
+    for track in tracks.iter() {
+        if markers.iter().any(|(_, marker, ..)| marker.0 == track.id) {
+            continue;
+        }
+        let material = materials.add(Color::GREEN);
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: marker_meshes.diamond.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(track.position.extend(0.5)),
+                ..default()
+            },
+            TrackMarker(track.id),
+            GameDespawnable,
+        )).with_children(|parent| {
+            parent.spawn((
+                MaterialMesh2dBundle {
+                    mesh: marker_meshes.leader.clone(),
+                    material,
+                    transform: Transform::from_xyz(0.0, 0.0, -0.1),
+                    ..default()
+                },
+                TrackLeader,
+            ));
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(track_label(track), TextStyle {
+                    font_size: 16.0,
+                    color: Color::GREEN,
+                    ..default()
+                }),
+                text_anchor: bevy::sprite::Anchor::BottomLeft,
+                transform: Transform::from_xyz(8.0, 8.0, 0.0),
+                ..default()
+            });
+        });
+    }
+}
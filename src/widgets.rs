@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use crate::actions::ActionSystems;
+use crate::actions::ActionState;
+use crate::actions::MenuAction;
+
+pub struct WidgetsPlugin;
+impl Plugin for WidgetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, (
+                hover_focus_system,
+                focus_navigation_system,
+                button_style_system,
+            ).chain().after(ActionSystems).after(UiSystem::Focus));
+    }
+}
+
+/// The button keyboard/gamepad navigation is currently on
+#[derive(Component)]
+pub struct Focused;
+
+/// Sent when a button carrying `A` is clicked, or activated with Enter / the gamepad confirm button.
+/// Screens register it with `add_event::<Activated<A>>()` and run `button_activation_system::<A>`.
+#[derive(Event)]
+pub struct Activated<A: Component + Clone>(pub A);
+
+pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+pub const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+pub const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+pub const FOCUSED_BORDER: Color = Color::rgb(0.35, 0.75, 0.35);
+pub const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+
+/// Style shared by every button: fixed size, thick border and centered label
+pub fn button_style(width: Val, height: Val) -> Style {
+    Style {
+        width,
+        height,
+        border: UiRect::all(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+pub fn button_text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        color: TEXT_COLOR,
+        ..default()
+    }
+}
+
+pub fn spawn_button<A: Component>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    action: A,
+    style: Style,
+    text_style: TextStyle,
+) {
+    parent.spawn((
+        ButtonBundle {
+            style,
+            border_color: BorderColor(Color::BLACK),
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        },
+        action,
+    )).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(label, text_style));
+    });
+}
+
+#[allow(clippy::type_complexity)]
+pub fn button_activation_system<A: Component + Clone>(
+    action_state: Res<ActionState>,
+    clicked: Query<(&Interaction, &A), (Changed<Interaction>, With<Button>)>,
+    focused: Query<&A, (With<Focused>, With<Button>)>,
+    mut activated: EventWriter<Activated<A>>,
+) {
+    for (interaction, action) in clicked.iter() {
+        if *interaction == Interaction::Pressed {
+            activated.send(Activated(action.clone()));
+        }
+    }
+    if action_state.menu_just_pressed(MenuAction::Confirm) {
+        for action in focused.iter() {
+            activated.send(Activated(action.clone()));
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn hover_focus_system(
+    mut commands: Commands,
+    hovered: Query<(Entity, &Interaction), (Changed<Interaction>, With<Button>, Without<Focused>)>,
+    focused: Query<Entity, With<Focused>>,
+) {
+    for (entity, interaction) in hovered.iter() {
+        if *interaction == Interaction::Hovered {
+            for old in focused.iter() {
+                commands.entity(old).remove::<Focused>();
+            }
+            commands.entity(entity).insert(Focused);
+        }
+    }
+}
+
+fn focus_navigation_system(
+    mut commands: Commands,
+    action_state: Res<ActionState>,
+    buttons: Query<(Entity, &GlobalTransform, Has<Focused>), With<Button>>,
+) {
+    // UI coordinates grow downwards, hence Up being -Y
+    let direction = if action_state.menu_just_pressed(MenuAction::Up) {
+        Some(Vec2::NEG_Y)
+    } else if action_state.menu_just_pressed(MenuAction::Down) {
+        Some(Vec2::Y)
+    } else if action_state.menu_just_pressed(MenuAction::Left) {
+        Some(Vec2::NEG_X)
+    } else if action_state.menu_just_pressed(MenuAction::Right) {
+        Some(Vec2::X)
+    } else {
+        None
+    };
+    let step = if action_state.menu_just_pressed(MenuAction::Next) {
+        Some(1)
+    } else if action_state.menu_just_pressed(MenuAction::Previous) {
+        Some(-1)
+    } else {
+        None
+    };
+    if direction.is_none() && step.is_none() {
+        return;
+    }
+
+    // Reading order: top to bottom, left to right
+    let mut ordered: Vec<(Entity, Vec2, bool)> = buttons.iter()
+        .map(|(entity, transform, focused)| (entity, transform.translation().xy(), focused))
+        .collect();
+    ordered.sort_by(|(_, a, _), (_, b, _)| (a.y, a.x).partial_cmp(&(b.y, b.x)).unwrap_or(std::cmp::Ordering::Equal));
+
+    let Some(current) = ordered.iter().position(|(_, _, focused)| *focused) else {
+        if let Some((entity, _, _)) = ordered.first() {
+            commands.entity(*entity).insert(Focused);
+        }
+        return;
+    };
+    let (current_entity, from, _) = ordered[current];
+
+    let next = if let Some(step) = step {
+        let len = ordered.len() as i32;
+        Some(ordered[(current as i32 + step).rem_euclid(len) as usize].0)
+    } else {
+        let direction = direction.unwrap_or_default();
+        ordered.iter()
+            .filter(|(entity, _, _)| *entity != current_entity)
+            .map(|(entity, position, _)| (*entity, *position - from))
+            .filter(|(_, offset)| offset.dot(direction) > 0.0)
+            .min_by(|(_, a), (_, b)| {
+                // Prefer buttons straight ahead over ones off to the side
+                let score_a = a.dot(direction) + a.perp_dot(direction).abs() * 2.0;
+                let score_b = b.dot(direction) + b.perp_dot(direction).abs() * 2.0;
+                score_a.partial_cmp(&score_b).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(entity, _)| entity)
+    };
+    if let Some(entity) = next {
+        commands.entity(current_entity).remove::<Focused>();
+        commands.entity(entity).insert(Focused);
+    }
+}
+
+fn button_style_system(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor, &mut BorderColor, Has<Focused>), With<Button>>,
+) {
+    for (interaction, mut color, mut border_color, focused) in buttons.iter_mut() {
+        let (background, border) = match (*interaction, focused) {
+            (Interaction::Pressed, _) => (PRESSED_BUTTON, Color::RED),
+            (Interaction::Hovered, _) => (HOVERED_BUTTON, Color::WHITE),
+            (Interaction::None, true) => (HOVERED_BUTTON, FOCUSED_BORDER),
+            (Interaction::None, false) => (NORMAL_BUTTON, Color::BLACK),
+        };
+        if color.0 != background {
+            color.0 = background;
+        }
+        if border_color.0 != border {
+            border_color.0 = border;
+        }
+    }
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::cli::Cli;
+use miles_below_darkness::enemy::Enemy;
+use miles_below_darkness::enemy::Wave;
+use miles_below_darkness::gamestate::GameState;
+use miles_below_darkness::replay::Playback;
+use miles_below_darkness::replay::Recording;
+use miles_below_darkness::settings::Difficulty;
+use miles_below_darkness::settings::Settings;
+use miles_below_darkness::simulation::GameRng;
+use miles_below_darkness::simulation::SimulationTick;
+
+fn parse(args: &[&str]) -> Result<Cli, String> {
+    Cli::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn parses_every_option() {
+    let cli = parse(&[
+        "--seed", "12", "--wave", "3", "--difficulty", "hard", "--size", "1280x720",
+        "--fullscreen", "--skip-menu", "--headless", "--ticks", "100", "--replay", "run.mbdr",
+        "--diagnostics-csv", "perf.csv",
+    ]).unwrap();
+    assert_eq!(cli.seed, Some(12));
+    assert_eq!(cli.wave, Some(3));
+    assert_eq!(cli.difficulty, Some(Difficulty::Hard));
+    assert_eq!(cli.size, Some((1280.0, 720.0)));
+    assert_eq!(cli.fullscreen, Some(true));
+    assert!(cli.skip_menu && cli.headless);
+    assert_eq!(cli.ticks, Some(100));
+    assert_eq!(cli.replay.unwrap().to_str(), Some("run.mbdr"));
+    assert_eq!(cli.diagnostics_csv.unwrap().to_str(), Some("perf.csv"));
+    assert_eq!(parse(&[]).unwrap(), Cli::default());
+}
+
+#[test]
+fn rejects_bad_options() {
+    assert!(parse(&["--seed"]).is_err());
+    assert!(parse(&["--seed", "abc"]).is_err());
+    assert!(parse(&["--wave", "0"]).is_err());
+    assert!(parse(&["--difficulty", "brutal"]).is_err());
+    assert!(parse(&["--size", "1280"]).is_err());
+    assert!(parse(&["--ticks", "10"]).is_err());
+    assert!(parse(&["--frobnicate"]).is_err());
+}
+
+#[test]
+fn skip_menu_starts_the_configured_game() {
+    let mut app = build_headless_app();
+    parse(&["--skip-menu", "--seed", "5", "--wave", "3", "--difficulty", "easy"]).unwrap()
+        .configure(&mut app).unwrap();
+    wait_for_state(&mut app, GameState::Game);
+
+    assert_eq!(app.world.resource::<GameRng>().seed(), 5);
+    assert_eq!(app.world.resource::<Settings>().difficulty, Difficulty::Easy);
+    assert_eq!(app.world.resource::<Wave>().current, 3);
+    assert_eq!(count::<Enemy>(&mut app), Difficulty::Easy.enemy_count() + 2);
+}
+
+#[test]
+fn pinned_seed_survives_going_back_to_the_menu() {
+    let mut app = build_headless_app();
+    parse(&["--seed", "5"]).unwrap().configure(&mut app).unwrap();
+    app.world.resource_mut::<GameRng>().reroll();
+    assert_eq!(app.world.resource::<GameRng>().seed(), 5);
+}
+
+#[test]
+fn clearing_a_wave_brings_the_next_one() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    despawn_all::<Enemy>(&mut app);
+    app.update();
+    app.update();
+
+    assert_eq!(app.world.resource::<Wave>().current, 2);
+    assert_eq!(count::<Enemy>(&mut app), Difficulty::Normal.enemy_count() + 1);
+}
+
+#[test]
+fn replay_file_from_the_command_line_plays_back() {
+    let mut app = build_headless_app();
+    parse(&["--skip-menu", "--wave", "2"]).unwrap().configure(&mut app).unwrap();
+    wait_for_state(&mut app, GameState::Game);
+    play_script(&mut app, 300);
+    let replay = app.world.resource::<Recording>().replay.clone().unwrap();
+    let expected = snapshot(&mut app);
+    let path = std::env::temp_dir().join(format!("miles-below-darkness-cli-{}.mbdr", std::process::id()));
+    replay.save(&path);
+
+    let mut app = build_headless_app();
+    let configured = parse(&["--replay", path.to_str().unwrap()]).unwrap().configure(&mut app);
+    let _ = std::fs::remove_file(&path);
+    configured.unwrap();
+    wait_for_state(&mut app, GameState::Game);
+    while app.world.resource::<SimulationTick>().0 < app.world.resource::<Playback>().ticks() {
+        app.update();
+    }
+    assert_eq!(app.world.resource::<Wave>().current, 2);
+    assert_eq!(snapshot(&mut app), expected);
+}
+
+#[test]
+fn diagnostics_csv_gets_a_row_per_frame() {
+    let path = std::env::temp_dir().join(format!("miles-below-darkness-diagnostics-{}.csv", std::process::id()));
+    let mut app = build_headless_app();
+    parse(&["--skip-menu", "--diagnostics-csv", path.to_str().unwrap()]).unwrap()
+        .configure(&mut app).unwrap();
+    wait_for_state(&mut app, GameState::Game);
+    for _ in 0..30 {
+        app.update();
+    }
+    // Dropping the app flushes the file
+    drop(app);
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let mut lines = csv.lines();
+    let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert!(header.contains(&"systems/collision_system"));
+    let last: Vec<&str> = lines.last().unwrap().split(',').collect();
+    assert_eq!(last.len(), header.len());
+    let enemies = header.iter().position(|column| *column == "entities/enemies").unwrap();
+    assert_eq!(last[enemies], Difficulty::Normal.enemy_count().to_string());
+}
@@ -0,0 +1,91 @@
+// Each test binary uses a different subset of these
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+use miles_below_darkness::enemy::Enemy;
+use miles_below_darkness::gamestate::GameState;
+use miles_below_darkness::player::Player;
+use miles_below_darkness::torpedo::Torpedo;
+
+pub fn wait_for_state(app: &mut App, state: GameState) {
+    for _ in 0..10 {
+        app.update();
+        if *app.world.resource::<State<GameState>>().get() == state {
+            return;
+        }
+    }
+    panic!("stuck in {:?}, expected {:?}", app.world.resource::<State<GameState>>().get(), state);
+}
+
+pub fn start_game(app: &mut App) {
+    app.update();
+    app.world.resource_mut::<NextState<GameState>>().set(GameState::Loading);
+    wait_for_state(app, GameState::Game);
+}
+
+pub fn run(app: &mut App, frames: u32) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+/// Presses `key` for one frame and lets go of it the next
+pub fn tap(app: &mut App, key: KeyCode) {
+    app.world.resource_mut::<ButtonInput<KeyCode>>().press(key);
+    app.update();
+    app.world.resource_mut::<ButtonInput<KeyCode>>().release(key);
+    app.update();
+}
+
+pub fn count<T: Component>(app: &mut App) -> usize {
+    app.world.query_filtered::<(), With<T>>().iter(&app.world).count()
+}
+
+pub fn hull(app: &mut App) -> i32 {
+    app.world.query::<&Player>().single(&app.world).hull()
+}
+
+pub fn despawn_all<T: Component>(app: &mut App) {
+    let entities: Vec<Entity> = app.world.query_filtered::<Entity, With<T>>().iter(&app.world).collect();
+    for entity in entities {
+        app.world.despawn(entity);
+    }
+}
+
+/// Exact positions and headings of everything that moves, for comparing runs bit for bit
+pub fn snapshot(app: &mut App) -> Vec<[u32; 4]> {
+    app.world.query_filtered::<&Transform, Or<(With<Player>, With<Enemy>, With<Torpedo>)>>()
+        .iter(&app.world)
+        .map(|transform| [
+            transform.translation.x.to_bits(),
+            transform.translation.y.to_bits(),
+            transform.rotation.z.to_bits(),
+            transform.rotation.w.to_bits(),
+        ])
+        .collect()
+}
+
+/// Steer left, fire, steer right, fire a guided torpedo, keyed by frame
+pub const SCRIPT: [(u32, KeyCode, bool); 8] = [
+    (0, KeyCode::KeyA, true),
+    (90, KeyCode::KeyA, false),
+    (130, KeyCode::Space, true),
+    (140, KeyCode::Space, false),
+    (200, KeyCode::KeyD, true),
+    (320, KeyCode::KeyD, false),
+    (400, KeyCode::ShiftLeft, true),
+    (410, KeyCode::ShiftLeft, false),
+];
+
+/// Runs `frames` frames of an already started game, pressing keys as `SCRIPT` says
+pub fn play_script(app: &mut App, frames: u32) {
+    for frame in 0..frames {
+        for (at, key, press) in SCRIPT {
+            if at == frame {
+                let mut input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+                if press { input.press(key) } else { input.release(key) }
+            }
+        }
+        app.update();
+    }
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use bevy::prelude::*;
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::console::Console;
+use miles_below_darkness::enemy::Enemy;
+use miles_below_darkness::gamestate::GameState;
+use miles_below_darkness::player::Player;
+use miles_below_darkness::player::PlayerHeart;
+use miles_below_darkness::torpedo::EnemyTorpedo;
+use miles_below_darkness::torpedo::FireRegularTorpedo;
+
+fn run(app: &mut App, line: &str) -> String {
+    let before = app.world.resource::<Console>().log().len();
+    app.world.resource_mut::<Console>().submit(line);
+    app.update();
+    app.world.resource::<Console>().log()[before..].join("\n")
+}
+
+#[test]
+fn spawn_adds_an_enemy_where_asked() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    let enemies = count::<Enemy>(&mut app);
+
+    run(&mut app, "spawn sub 100 -50");
+    assert_eq!(count::<Enemy>(&mut app), enemies + 1);
+    let spawned = app.world.query_filtered::<&Transform, With<Enemy>>()
+        .iter(&app.world)
+        .any(|transform| transform.translation.distance(Vec3::new(100.0, -50.0, 0.0)) < 1.0);
+    assert!(spawned, "no enemy at 100 -50");
+}
+
+#[test]
+fn mistakes_are_reported_without_running_anything() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    let enemies = count::<Enemy>(&mut app);
+
+    assert!(run(&mut app, "spawn whale 0 0").contains("unknown archetype"));
+    assert!(run(&mut app, "spawn sub 0").contains("missing <y>"));
+    assert!(run(&mut app, "teleport").contains("unknown command"));
+    assert_eq!(count::<Enemy>(&mut app), enemies);
+    assert!(run(&mut app, "help").contains("spawn <archetype> <x> <y>"));
+}
+
+#[test]
+fn life_sets_hull_and_hearts() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+
+    run(&mut app, "life 8");
+    assert_eq!(hull(&mut app), 8);
+    assert_eq!(count::<PlayerHeart>(&mut app), 8);
+    run(&mut app, "life 2");
+    assert_eq!(hull(&mut app), 2);
+    assert_eq!(count::<PlayerHeart>(&mut app), 2);
+}
+
+#[test]
+fn god_mode_ignores_hits() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    despawn_all::<Enemy>(&mut app);
+
+    let lives = hull(&mut app);
+    assert!(run(&mut app, "god").contains("on"));
+    app.world.send_event(FireRegularTorpedo {
+        from: Vec2::new(0.0, 150.0),
+        towards: Vec2::new(0.0, -1.0),
+    });
+    for _ in 0..600 {
+        app.update();
+        if count::<EnemyTorpedo>(&mut app) == 0 {
+            break;
+        }
+    }
+    app.update();
+    app.update();
+    assert_eq!(hull(&mut app), lives);
+}
+
+#[test]
+fn state_switches_the_game_state() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+
+    run(&mut app, "state pause");
+    wait_for_state(&mut app, GameState::Pause);
+    assert!(run(&mut app, "count").contains("Player"));
+}
+
+#[test]
+fn state_game_from_the_menu_loads_a_game_first() {
+    let mut app = build_headless_app();
+    app.update();
+
+    assert!(run(&mut app, "state pause").contains("no game running"));
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Menu);
+    assert!(run(&mut app, "state game").contains("Loading"));
+    wait_for_state(&mut app, GameState::Game);
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(count::<Player>(&mut app), 1);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::ecs::system::CommandQueue;
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::decoy::spawn_decoy;
+use miles_below_darkness::decoy::Decoy;
+use miles_below_darkness::decoy::EnemyDecoy;
+use miles_below_darkness::decoy::PlayerDecoy;
+use miles_below_darkness::enemy::Enemy;
+use miles_below_darkness::player::Player;
+use miles_below_darkness::torpedo::guided_target;
+use miles_below_darkness::torpedo::EnemyTorpedo;
+use miles_below_darkness::torpedo::FireGuidedTorpedo;
+use miles_below_darkness::torpedo::GuidedTorpedo;
+use miles_below_darkness::torpedo::Lure;
+use miles_below_darkness::torpedo::PlayerTorpedo;
+use miles_below_darkness::torpedo::RegularTorpedo;
+
+fn entities<F: bevy::ecs::query::QueryFilter>(app: &mut App) -> Vec<Entity> {
+    app.world.query_filtered::<Entity, F>().iter(&app.world).collect()
+}
+
+#[test]
+fn seekers_weigh_decoys_against_boats() {
+    let boat = Vec3::new(100.0, 0.0, 0.0);
+    let decoy = Vec3::new(0.0, 150.0, 0.0);
+    assert_eq!(guided_target(&Lure::gather([boat], []), Vec3::ZERO), Some(boat));
+    // Louder, so it wins from farther away
+    assert_eq!(guided_target(&Lure::gather([boat], [decoy]), Vec3::ZERO), Some(decoy));
+    // Unless the boat is much closer
+    let close = Vec3::new(20.0, 0.0, 0.0);
+    assert_eq!(guided_target(&Lure::gather([close], [decoy]), Vec3::ZERO), Some(close));
+    // Out of seeker range it isn't heard at all
+    assert_eq!(guided_target(&Lure::gather([], [Vec3::new(500.0, 0.0, 0.0)]), Vec3::ZERO), None);
+}
+
+#[test]
+fn player_decoys_drift_astern_and_run_down() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.world.resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyF);
+    run(&mut app, 9 * 60);
+    app.world.resource_mut::<ButtonInput<KeyCode>>().release(KeyCode::KeyF);
+    // Held past the reload, but only pressing it drops a decoy
+    let decoys = entities::<With<PlayerDecoy>>(&mut app);
+    assert_eq!(decoys.len(), 1);
+
+    let player = app.world.query_filtered::<&Transform, With<Player>>().single(&app.world).translation;
+    let decoy = app.world.get::<Transform>(decoys[0]).unwrap().translation;
+    assert!(decoy.y < player.y, "{:?} is not astern of {:?}", decoy, player);
+
+    run(&mut app, 12 * 60);
+    assert!(app.world.get_entity(decoys[0]).is_none());
+}
+
+#[test]
+fn enemy_guided_torpedo_is_seduced_by_a_decoy() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    let before = entities::<(With<GuidedTorpedo>, With<EnemyTorpedo>)>(&mut app);
+    app.world.send_event(FireGuidedTorpedo { from: Vec2::new(150.0, 0.0), towards: Vec2::new(-1.0, 0.0) });
+    let mut queue = CommandQueue::default();
+    spawn_decoy(&mut Commands::new(&mut queue, &app.world), &Transform::from_xyz(150.0, 180.0, 0.0), true);
+    queue.apply(&mut app.world);
+    app.update();
+    let torpedo = entities::<(With<GuidedTorpedo>, With<EnemyTorpedo>)>(&mut app)
+        .into_iter()
+        .find(|torpedo| !before.contains(torpedo))
+        .expect("no torpedo was launched");
+    let decoy = entities::<With<PlayerDecoy>>(&mut app)[0];
+
+    // The decoy is 180 from where the torpedo started and the player 150, but the decoy is louder
+    run(&mut app, 8 * 60);
+    assert!(app.world.get_entity(torpedo).is_none());
+    assert!(app.world.get_entity(decoy).is_none());
+    assert_eq!(count::<Decoy>(&mut app), 0);
+}
+
+/// Fires with `key` and moves the torpedo to just inside seeker range of an enemy
+fn close_on_an_enemy<T: Component>(app: &mut App, key: KeyCode) {
+    app.world.resource_mut::<ButtonInput<KeyCode>>().press(key);
+    let torpedo = loop {
+        app.update();
+        if let Some(torpedo) = entities::<(With<T>, With<PlayerTorpedo>)>(app).first() {
+            break *torpedo;
+        }
+    };
+    app.world.resource_mut::<ButtonInput<KeyCode>>().release(key);
+    let enemy = app.world.query_filtered::<&Transform, With<Enemy>>().iter(&app.world).next().unwrap().translation;
+    app.world.get_mut::<Transform>(torpedo).unwrap().translation = enemy + Vec3::new(150.0, 0.0, 0.0);
+    run(app, 2);
+}
+
+#[test]
+fn enemies_drop_decoys_when_a_guided_torpedo_closes() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    assert_eq!(count::<EnemyDecoy>(&mut app), 0);
+
+    // A straight runner can't be fooled, so it isn't worth a decoy
+    close_on_an_enemy::<RegularTorpedo>(&mut app, KeyCode::Space);
+    assert_eq!(count::<EnemyDecoy>(&mut app), 0);
+
+    close_on_an_enemy::<GuidedTorpedo>(&mut app, KeyCode::ShiftLeft);
+    assert!(count::<EnemyDecoy>(&mut app) >= 1);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use bevy::prelude::*;
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::biologics::Biologic;
+use miles_below_darkness::enemy::Enemy;
+use miles_below_darkness::firecontrol::FiringSolution;
+use miles_below_darkness::firecontrol::solve;
+use miles_below_darkness::torpedo::GuidedTorpedo;
+use miles_below_darkness::torpedo::PlayerTorpedo;
+use miles_below_darkness::torpedo::RegularTorpedo;
+use miles_below_darkness::torpedo::TrackTarget;
+use miles_below_darkness::sonar::SonarNoise;
+use miles_below_darkness::tracking::Classification;
+use miles_below_darkness::tracking::Tracks;
+
+/// Plays for `frames` with perfect sonar, then designates the first track of a contact with component `T`
+fn designate_a<T: Component>(app: &mut App, frames: u32) -> u32 {
+    start_game(app);
+    app.insert_resource(SonarNoise::NONE);
+    run(app, frames);
+    for _ in 0..20 {
+        tap(app, KeyCode::KeyQ);
+        let selected = app.world.resource::<Tracks>().selected().and_then(|track| track.entity);
+        if selected.is_some_and(|entity| app.world.get::<T>(entity).is_some()) {
+            tap(app, KeyCode::KeyE);
+            return app.world.resource::<Tracks>().designated().expect("nothing designated").id;
+        }
+    }
+    panic!("no track to designate");
+}
+
+fn designate_a_track(app: &mut App) -> u32 {
+    designate_a::<Enemy>(app, 300)
+}
+
+/// Holds `key` until a torpedo of kind `T` is in the water
+fn fire<T: Component>(app: &mut App, key: KeyCode) -> Entity {
+    app.world.resource_mut::<ButtonInput<KeyCode>>().press(key);
+    for _ in 0..180 {
+        app.update();
+        let fired = app.world.query_filtered::<Entity, (With<T>, With<PlayerTorpedo>)>().iter(&app.world).next();
+        if let Some(torpedo) = fired {
+            app.world.resource_mut::<ButtonInput<KeyCode>>().release(key);
+            return torpedo;
+        }
+    }
+    panic!("nothing was fired");
+}
+
+#[test]
+fn stationary_target_needs_no_lead() {
+    let solution = solve(1, Vec2::ZERO, Vec2::new(0.0, 100.0), Vec2::ZERO, 10.0).unwrap();
+    assert!(solution.bearing.abs() < 1e-5);
+    assert!(solution.lead_angle.abs() < 1e-5);
+    assert!((solution.time_to_intercept - 10.0).abs() < 1e-4);
+}
+
+#[test]
+fn crossing_target_is_led() {
+    let velocity = Vec2::new(5.0, 0.0);
+    let solution = solve(1, Vec2::ZERO, Vec2::new(0.0, 100.0), velocity, 10.0).unwrap();
+    // Torpedo and target reach the intercept point at the same moment
+    let torpedo_run = solution.intercept.length();
+    assert!((torpedo_run - 10.0 * solution.time_to_intercept).abs() < 1e-3);
+    assert!((solution.intercept - (Vec2::new(0.0, 100.0) + velocity * solution.time_to_intercept)).length() < 1e-3);
+    // Target moves right, so aim right of it, which is clockwise
+    assert!(solution.lead_angle < -0.1);
+}
+
+#[test]
+fn faster_target_running_away_has_no_solution() {
+    assert!(solve(1, Vec2::ZERO, Vec2::new(0.0, 100.0), Vec2::new(0.0, 20.0), 10.0).is_none());
+}
+
+#[test]
+fn regular_torpedo_fires_on_the_intercept_bearing() {
+    let mut app = build_headless_app();
+    designate_a_track(&mut app);
+    let torpedo = fire::<RegularTorpedo>(&mut app, KeyCode::Space);
+
+    let solution = app.world.resource::<FiringSolution>().0.expect("no firing solution");
+    let rotation = app.world.get::<Transform>(torpedo).unwrap().rotation;
+    assert!(rotation.angle_between(Quat::from_rotation_z(solution.bearing)) < 1e-3);
+}
+
+#[test]
+fn guided_torpedo_is_launched_onto_the_designated_track() {
+    let mut app = build_headless_app();
+    let track = designate_a_track(&mut app);
+    let torpedo = fire::<GuidedTorpedo>(&mut app, KeyCode::ShiftLeft);
+
+    assert_eq!(app.world.get::<TrackTarget>(torpedo).map(|target| target.0), Some(track));
+}
+
+#[test]
+fn guided_torpedo_ignores_a_track_classified_harmless() {
+    let mut app = build_headless_app();
+    let track = designate_a::<Biologic>(&mut app, 900);
+    assert_eq!(app.world.resource::<Tracks>().get(track).unwrap().classification, Classification::Biologic);
+    let torpedo = fire::<GuidedTorpedo>(&mut app, KeyCode::ShiftLeft);
+
+    assert!(app.world.get::<TrackTarget>(torpedo).is_none());
+}
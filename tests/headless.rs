@@ -0,0 +1,135 @@
+mod common;
+
+use bevy::prelude::*;
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::enemy::Enemy;
+use miles_below_darkness::player::Player;
+use miles_below_darkness::player::PlayerHeart;
+use miles_below_darkness::settings::Difficulty;
+use miles_below_darkness::settings::Settings;
+use miles_below_darkness::simulation::GameRng;
+use miles_below_darkness::sonar::Sonar;
+use miles_below_darkness::torpedo::EnemyTorpedo;
+use miles_below_darkness::torpedo::FireRegularTorpedo;
+use miles_below_darkness::torpedo::PlayerTorpedo;
+
+#[test]
+fn loading_reaches_game_with_everything_spawned() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+
+    assert_eq!(count::<Player>(&mut app), 1);
+    assert_eq!(count::<Sonar>(&mut app), 1);
+    assert_eq!(count::<Enemy>(&mut app), Difficulty::Normal.enemy_count());
+    assert_eq!(count::<PlayerHeart>(&mut app) as i32, hull(&mut app));
+}
+
+#[test]
+fn difficulty_sets_enemy_count() {
+    let mut app = build_headless_app();
+    app.world.resource_mut::<Settings>().difficulty = Difficulty::Hard;
+    start_game(&mut app);
+
+    assert_eq!(count::<Enemy>(&mut app), Difficulty::Hard.enemy_count());
+}
+
+#[test]
+fn enemies_move_inside_the_sonar() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    despawn_all::<EnemyTorpedo>(&mut app);
+
+    let before: Vec<Vec3> = app.world.query_filtered::<&Transform, With<Enemy>>()
+        .iter(&app.world)
+        .map(|transform| transform.translation)
+        .collect();
+    for _ in 0..120 {
+        app.update();
+    }
+    let after: Vec<Vec3> = app.world.query_filtered::<&Transform, With<Enemy>>()
+        .iter(&app.world)
+        .map(|transform| transform.translation)
+        .collect();
+
+    let radius = app.world.query::<&Sonar>().single(&app.world).radius;
+    assert_eq!(before.len(), after.len());
+    for (from, to) in before.iter().zip(after.iter()) {
+        assert!(from.distance(*to) > 1.0, "enemy at {from} did not move");
+        assert!(to.length() < radius, "enemy left the sonar at {to}");
+    }
+}
+
+#[test]
+fn player_torpedo_leaves_the_bow() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    despawn_all::<Enemy>(&mut app);
+
+    app.world.resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::Space);
+    // The tubes start on a two second cooldown
+    for _ in 0..130 {
+        app.update();
+    }
+    app.world.resource_mut::<ButtonInput<KeyCode>>().release(KeyCode::Space);
+    assert!(count::<PlayerTorpedo>(&mut app) >= 1);
+
+    let start = app.world.query_filtered::<&Transform, With<PlayerTorpedo>>()
+        .iter(&app.world)
+        .next()
+        .unwrap()
+        .translation;
+    for _ in 0..60 {
+        app.update();
+    }
+    let end = app.world.query_filtered::<&Transform, With<PlayerTorpedo>>()
+        .iter(&app.world)
+        .next()
+        .unwrap()
+        .translation;
+    assert!(end.y > start.y, "torpedo went from {start} to {end}");
+}
+
+#[test]
+fn enemy_torpedo_hits_the_player_once() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    despawn_all::<Enemy>(&mut app);
+
+    let lives = hull(&mut app);
+    app.world.send_event(FireRegularTorpedo {
+        from: Vec2::new(0.0, 150.0),
+        towards: Vec2::new(0.0, -1.0),
+    });
+    for _ in 0..600 {
+        app.update();
+        if count::<EnemyTorpedo>(&mut app) == 0 {
+            break;
+        }
+    }
+
+    assert_eq!(count::<EnemyTorpedo>(&mut app), 0, "torpedo never reached the player");
+    // Damage is applied from the collision event, which may only be read on the next frame
+    app.update();
+    app.update();
+    assert_eq!(hull(&mut app), lives - 1);
+    assert_eq!(count::<PlayerHeart>(&mut app) as i32, lives - 1);
+}
+
+fn play_scripted(seed: u64) -> Vec<[u32; 4]> {
+    let mut app = build_headless_app();
+    app.insert_resource(GameRng::new(seed));
+    start_game(&mut app);
+    play_script(&mut app, 900);
+    snapshot(&mut app)
+}
+
+#[test]
+fn same_seed_and_inputs_give_identical_runs() {
+    assert_eq!(play_scripted(7), play_scripted(7));
+}
+
+#[test]
+fn different_seeds_give_different_runs() {
+    assert_ne!(play_scripted(7), play_scripted(8));
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use bevy::prelude::*;
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::gamestate::GameState;
+use miles_below_darkness::replay::Playback;
+use miles_below_darkness::replay::Recording;
+use miles_below_darkness::replay::Replay;
+use miles_below_darkness::replay::StartReplay;
+use miles_below_darkness::replay::TickInput;
+use miles_below_darkness::settings::Difficulty;
+use miles_below_darkness::settings::Settings;
+use miles_below_darkness::simulation::GameRng;
+use miles_below_darkness::simulation::SimulationTick;
+
+/// Plays the scripted game and returns its recording and final state
+fn record(frames: u32) -> (Replay, Vec<[u32; 4]>) {
+    let mut app = build_headless_app();
+    app.insert_resource(GameRng::new(42));
+    app.world.resource_mut::<Settings>().difficulty = Difficulty::Hard;
+    start_game(&mut app);
+    play_script(&mut app, frames);
+    let replay = app.world.resource::<Recording>().replay.clone().unwrap();
+    (replay, snapshot(&mut app))
+}
+
+fn start_replay(replay: Replay) -> App {
+    let mut app = build_headless_app();
+    app.update();
+    app.world.send_event(StartReplay(replay));
+    wait_for_state(&mut app, GameState::Game);
+    app
+}
+
+fn run_until_tick(app: &mut App, tick: u32) {
+    for _ in 0..10_000 {
+        if app.world.resource::<SimulationTick>().0 >= tick && !app.world.resource::<Playback>().seeking() {
+            return;
+        }
+        app.update();
+    }
+    panic!("never reached tick {}", tick);
+}
+
+#[test]
+fn recording_survives_encoding() {
+    let (replay, _) = record(600);
+    // One tick per frame, plus the one run by the frame that entered Game
+    assert_eq!(replay.ticks(), 601);
+    assert_eq!(replay.seed, 42);
+    assert_eq!(replay.difficulty, Difficulty::Hard);
+    let bytes = replay.encode();
+    // A handful of input changes, not one entry per tick
+    assert!(bytes.len() < 200, "{} bytes", bytes.len());
+    assert_eq!(Replay::decode(&bytes).unwrap(), replay);
+}
+
+#[test]
+fn corrupt_replays_are_rejected() {
+    let (replay, _) = record(60);
+    let bytes = replay.encode();
+    assert!(Replay::decode(&bytes[..bytes.len() - 1]).is_err());
+    assert!(Replay::decode(b"not a replay").is_err());
+}
+
+#[test]
+fn malformed_input_runs_are_rejected() {
+    let mut replay = Replay::new(1, Difficulty::Normal, 1);
+    replay.inputs.push(TickInput::default());
+    let bytes = replay.encode();
+    assert!(Replay::decode(&bytes).is_ok());
+    // The header is 22 bytes, then each run is a length, a rotation and the buttons
+    let (header, run) = bytes.split_at(22);
+
+    let mut empty = bytes.clone();
+    empty[22..24].copy_from_slice(&0u16.to_le_bytes());
+    assert!(Replay::decode(&empty).is_err());
+
+    let mut unknown_button = bytes.clone();
+    unknown_button[28] = 0x80;
+    assert!(Replay::decode(&unknown_button).is_err());
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(Replay::decode(&trailing).is_err());
+
+    // A few kilobytes of the longest runs would otherwise expand into over a billion ticks
+    let runs = 20_000u32;
+    let mut huge = header[..18].to_vec();
+    huge.extend_from_slice(&runs.to_le_bytes());
+    for _ in 0..runs {
+        huge.extend_from_slice(&u16::MAX.to_le_bytes());
+        huge.extend_from_slice(&run[2..]);
+    }
+    assert!(Replay::decode(&huge).is_err());
+}
+
+#[test]
+fn replays_from_other_versions_are_rejected() {
+    let (replay, _) = record(60);
+    let mut bytes = replay.encode();
+    // Right after the magic number
+    bytes[4] -= 1;
+    assert!(Replay::decode(&bytes).is_err());
+}
+
+#[test]
+fn replay_reproduces_the_recorded_game() {
+    let (replay, recorded) = record(900);
+    let ticks = replay.ticks();
+    let mut app = start_replay(Replay::decode(&replay.encode()).unwrap());
+    run_until_tick(&mut app, ticks);
+
+    assert_eq!(app.world.resource::<SimulationTick>().0, ticks);
+    assert_eq!(snapshot(&mut app), recorded);
+    // Replays are not recorded themselves
+    assert!(app.world.resource::<Recording>().replay.is_none());
+}
+
+#[test]
+fn seeking_back_and_forth_lands_on_the_same_state() {
+    let (replay, _) = record(900);
+    let mut reference = start_replay(replay.clone());
+    run_until_tick(&mut reference, 300);
+    let expected = snapshot(&mut reference);
+
+    let mut app = start_replay(replay);
+    run_until_tick(&mut app, 600);
+    app.world.resource_mut::<Playback>().seek(300);
+    run_until_tick(&mut app, 300);
+    assert_eq!(app.world.resource::<SimulationTick>().0, 300);
+    assert_eq!(snapshot(&mut app), expected);
+
+    app.world.resource_mut::<Playback>().seek(100);
+    run_until_tick(&mut app, 100);
+    app.world.resource_mut::<Playback>().seek(300);
+    run_until_tick(&mut app, 300);
+    assert_eq!(app.world.resource::<SimulationTick>().0, 300);
+    assert_eq!(snapshot(&mut app), expected);
+}
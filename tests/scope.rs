@@ -0,0 +1,73 @@
+mod common;
+
+use bevy::prelude::*;
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::scope::compass;
+use miles_below_darkness::scope::ContactReading;
+use miles_below_darkness::scope::ContactReadout;
+use miles_below_darkness::scope::ScopeLabel;
+use miles_below_darkness::simulation::heading;
+use miles_below_darkness::simulation::TICK_RATE;
+use miles_below_darkness::sonar::Sonar;
+use miles_below_darkness::tracking::Tracks;
+
+fn readout(app: &mut App) -> String {
+    app.world.query_filtered::<&Text, With<ContactReadout>>().single(&app.world).sections[0].value.clone()
+}
+
+#[test]
+fn compass_bearings_run_clockwise_from_north() {
+    assert_eq!(compass(heading(Vec2::Y)), 0.0);
+    assert!((compass(heading(Vec2::X)) - 90.0).abs() < 1e-3);
+    assert!((compass(heading(-Vec2::Y)) - 180.0).abs() < 1e-3);
+    assert!((compass(heading(-Vec2::X)) - 270.0).abs() < 1e-3);
+}
+
+#[test]
+fn reading_a_closing_contact() {
+    let mut tracks = Tracks::default();
+    // Due east, heading straight for us at 5 units per second
+    tracks.detect(None, Vec2::new(100.0, 0.0), None, 0);
+    tracks.detect(None, Vec2::new(95.0, 0.0), None, TICK_RATE as u32);
+
+    let track = tracks.iter().next().unwrap();
+    let reading = ContactReading::new(Vec2::ZERO, track, TICK_RATE as u32);
+    assert!((reading.bearing - 90.0).abs() < 1e-3);
+    assert!((reading.range - 95.0).abs() < 1e-3);
+    assert!((reading.closure_rate - 5.0).abs() < 1e-3);
+}
+
+#[test]
+fn range_labels_follow_the_sonar_radius() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.world.query::<&mut Sonar>().single_mut(&mut app.world).radius = 400.0;
+    app.update();
+
+    let mut ranges: Vec<String> = app.world.query::<(&ScopeLabel, &Text)>()
+        .iter(&app.world)
+        .filter(|(label, _)| matches!(label, ScopeLabel::Range(_)))
+        .map(|(_, text)| text.sections[0].value.clone())
+        .collect();
+    ranges.sort();
+    assert_eq!(ranges, ["100", "200", "300"]);
+}
+
+#[test]
+fn readout_shows_heading_and_the_selected_contact() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.update();
+    assert_eq!(readout(&mut app), "OWN HDG 000 SONAR LONG\nNO CONTACT SELECTED");
+
+    for _ in 0..300 {
+        app.update();
+    }
+    app.world.resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyQ);
+    app.update();
+    app.update();
+    let id = app.world.resource::<Tracks>().selected().expect("nothing selected").id;
+    let text = readout(&mut app);
+    assert!(text.contains(&format!("\nT{} ", id)) && text.contains(" BRG "), "{}", text);
+}
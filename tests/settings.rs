@@ -0,0 +1,96 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::input::ButtonState;
+use bevy::input::keyboard::Key;
+use bevy::input::keyboard::KeyboardInput;
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::actions::Action;
+use miles_below_darkness::actions::Bindings;
+use miles_below_darkness::gamestate::GameState;
+use miles_below_darkness::settings::Difficulty;
+use miles_below_darkness::settings::Settings;
+use miles_below_darkness::settings::SettingsButtonAction;
+use miles_below_darkness::widgets::Focused;
+
+// Goes through the input events like a real keyboard, so the key also shows up as just pressed
+fn type_key(app: &mut App, key_code: KeyCode, logical_key: Key) {
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        app.world.send_event(KeyboardInput {
+            key_code,
+            logical_key: logical_key.clone(),
+            state,
+            window: Entity::PLACEHOLDER,
+        });
+        app.update();
+    }
+}
+
+#[test]
+fn rebinding_takes_the_key_from_other_actions() {
+    let mut bindings = Bindings::default();
+    assert!(bindings.rebind(Action::FireRegular, KeyCode::KeyQ));
+    assert_eq!(bindings.keys(Action::FireRegular), [KeyCode::KeyQ]);
+    assert!(bindings.keys(Action::CycleTrack).is_empty());
+}
+
+#[test]
+fn rebinding_never_leaves_pause_without_a_key() {
+    let mut bindings = Bindings::default();
+    assert!(!bindings.rebind(Action::FireRegular, KeyCode::Escape));
+    assert_eq!(bindings.keys(Action::Pause), [KeyCode::Escape]);
+    assert_eq!(bindings.keys(Action::FireRegular), [KeyCode::Space]);
+
+    // Once pause has another key, Escape is free to go
+    assert!(bindings.rebind(Action::Pause, KeyCode::KeyP));
+    assert!(bindings.rebind(Action::FireRegular, KeyCode::Escape));
+    assert_eq!(bindings.keys(Action::Pause), [KeyCode::KeyP]);
+}
+
+#[test]
+fn old_binding_files_keep_new_defaults_off_their_keys() {
+    // Saved before tracking, sonar modes and decoys had actions of their own
+    let bindings = Bindings::parse("rotate_left = KeyA\nrotate_right = KeyD\nfire_regular = KeyQ\nfire_guided = KeyE\nfire_counter = KeyF\npause = Escape\n");
+    assert_eq!(bindings.keys(Action::FireRegular), [KeyCode::KeyQ]);
+    assert_eq!(bindings.keys(Action::FireGuided), [KeyCode::KeyE]);
+    assert!(bindings.keys(Action::CycleTrack).is_empty());
+    assert!(bindings.keys(Action::DesignateTrack).is_empty());
+    assert!(bindings.keys(Action::DeployDecoy).is_empty());
+    // Nobody took R
+    assert_eq!(bindings.keys(Action::SonarMode), [KeyCode::KeyR]);
+}
+
+#[test]
+fn enter_on_a_rebind_button_waits_for_the_next_key() {
+    let mut app = build_headless_app();
+    app.update();
+    app.world.resource_mut::<NextState<GameState>>().set(GameState::Settings);
+    wait_for_state(&mut app, GameState::Settings);
+    let button = app.world.query::<(Entity, &SettingsButtonAction)>()
+        .iter(&app.world)
+        .find(|(_, action)| matches!(action, SettingsButtonAction::Rebind(Action::FireRegular)))
+        .map(|(entity, _)| entity)
+        .expect("no rebind button for firing");
+    app.world.entity_mut(button).insert(Focused);
+
+    type_key(&mut app, KeyCode::Enter, Key::Enter);
+    assert_eq!(app.world.resource::<Bindings>().keys(Action::FireRegular), [KeyCode::Space]);
+    type_key(&mut app, KeyCode::KeyK, Key::Character("k".into()));
+    assert_eq!(app.world.resource::<Bindings>().keys(Action::FireRegular), [KeyCode::KeyK]);
+}
+
+#[test]
+fn unusable_resolutions_fall_back_to_the_default() {
+    let default = Settings::default().resolution;
+    assert_eq!(Settings::parse("resolution = 1600x900").resolution, (1600.0, 900.0));
+    for line in ["resolution = 0x0", "resolution = -1280x720", "resolution = NaNx720", "resolution = 1280xinf"] {
+        assert_eq!(Settings::parse(line).resolution, default, "{}", line);
+    }
+}
+
+#[test]
+fn harder_enemies_fire_more_guided_torpedoes() {
+    assert_eq!(Difficulty::Easy.guided_every(), None);
+    assert!(Difficulty::Normal.guided_every() > Difficulty::Hard.guided_every());
+}
@@ -0,0 +1,217 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::ecs::event::ManualEventReader;
+use bevy::utils::HashSet;
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::console::Console;
+use miles_below_darkness::enemy::Enemy;
+use miles_below_darkness::sonar::in_sweep;
+use miles_below_darkness::sonar::PingEvent;
+use miles_below_darkness::sonar::Line;
+use miles_below_darkness::sonar::Sonar;
+use miles_below_darkness::sonar::SonarMode;
+use miles_below_darkness::sonar::SonarNoise;
+use miles_below_darkness::sonar::FalseContact;
+use miles_below_darkness::sonar::Echo;
+use miles_below_darkness::sonar::Signature;
+use miles_below_darkness::biologics::Biologic;
+use miles_below_darkness::sonar::SECTOR_WIDTH;
+
+fn sonar(app: &mut App) -> (SonarMode, f32, f32) {
+    let sonar = app.world.query::<&Sonar>().single(&app.world);
+    (sonar.mode, sonar.radius, sonar.range())
+}
+
+/// Compass degrees from the scope's center to the middle of the sweep line, and how far that is
+fn sweep(app: &mut App) -> (f32, f32) {
+    let center = app.world.query_filtered::<&Transform, With<Sonar>>().single(&app.world).translation.xy();
+    let line = app.world.query_filtered::<&Transform, With<Line>>().single(&app.world).translation.xy();
+    let offset = line - center;
+    (offset.x.atan2(offset.y).to_degrees(), offset.length())
+}
+
+#[test]
+fn modes_cycle_and_resize_the_sweep() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    let (mode, radius, range) = sonar(&mut app);
+    assert_eq!(mode, SonarMode::LongRange);
+    assert_eq!(range, radius);
+
+    tap(&mut app, KeyCode::KeyR);
+    let (mode, _, range) = sonar(&mut app);
+    assert_eq!(mode, SonarMode::ShortRange);
+    assert_eq!(range, radius / 2.0);
+    assert!((sweep(&mut app).1 - range / 2.0).abs() < 1e-3);
+
+    tap(&mut app, KeyCode::KeyR);
+    tap(&mut app, KeyCode::KeyR);
+    assert_eq!(sonar(&mut app).0, SonarMode::LongRange);
+}
+
+#[test]
+fn holding_the_key_switches_once() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.world.resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyR);
+    run(&mut app, 30);
+    assert_eq!(sonar(&mut app).0, SonarMode::ShortRange);
+}
+
+#[test]
+fn sector_scan_stays_around_the_bow() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    tap(&mut app, KeyCode::KeyR);
+    tap(&mut app, KeyCode::KeyR);
+    assert_eq!(sonar(&mut app).0, SonarMode::Sector);
+
+    let (mut lowest, mut highest) = (f32::MAX, f32::MIN);
+    for _ in 0..300 {
+        app.update();
+        let (bearing, _) = sweep(&mut app);
+        lowest = lowest.min(bearing);
+        highest = highest.max(bearing);
+    }
+    // The boat hasn't turned, so the arc is centered on north and swept end to end
+    assert!(lowest >= -SECTOR_WIDTH / 2.0 - 1e-3 && highest <= SECTOR_WIDTH / 2.0 + 1e-3, "{} to {}", lowest, highest);
+    assert!(lowest < -SECTOR_WIDTH / 2.0 + 5.0 && highest > SECTOR_WIDTH / 2.0 - 5.0, "{} to {}", lowest, highest);
+}
+
+#[test]
+fn sweeps_cover_the_sector_between_ticks() {
+    let quarter = std::f32::consts::FRAC_PI_2;
+    // Due east, swept clockwise from north to east and beyond
+    assert!(in_sweep(Vec2::new(100.0, 0.0), quarter, quarter, 200.0));
+    assert!(in_sweep(Vec2::new(100.0, 0.0), quarter + 0.1, 0.2, 200.0));
+    // Not yet reached, already passed, out of range
+    assert!(!in_sweep(Vec2::new(100.0, 0.0), quarter - 0.1, 0.2, 200.0));
+    assert!(!in_sweep(Vec2::new(100.0, 0.0), quarter + 0.3, 0.2, 200.0));
+    assert!(!in_sweep(Vec2::new(100.0, 0.0), quarter, quarter, 50.0));
+    // Counter-clockwise, and across north
+    assert!(in_sweep(Vec2::new(100.0, 0.0), quarter - 0.1, -0.2, 200.0));
+    assert!(in_sweep(Vec2::new(-1.0, 100.0), 0.1, 0.2, 200.0));
+    // Nothing is found while the sweep stands still
+    assert!(!in_sweep(Vec2::new(100.0, 0.0), quarter, 0.0, 200.0));
+}
+
+#[test]
+fn fast_sweeps_do_not_skip_contacts() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.insert_resource(SonarNoise::NONE);
+    // Most of a radian per tick, far wider than any contact
+    app.world.resource_mut::<Console>().submit("sweep 50");
+    let mut events = ManualEventReader::<PingEvent>::default();
+    let mut pinged = HashSet::new();
+    for _ in 0..10 {
+        app.update();
+        pinged.extend(events.read(app.world.resource::<Events<PingEvent>>()).map(|event| event.entity));
+    }
+
+    let (radius, center) = {
+        let (sonar, transform) = app.world.query::<(&Sonar, &Transform)>().single(&app.world);
+        (sonar.range(), transform.translation.xy())
+    };
+    let in_range: Vec<Entity> = app.world.query_filtered::<(Entity, &Transform), With<Enemy>>()
+        .iter(&app.world)
+        .filter(|(_, transform)| transform.translation.xy().distance(center) < radius - 10.0)
+        .map(|(entity, _)| entity)
+        .collect();
+    assert!(!in_range.is_empty());
+    for enemy in in_range {
+        assert!(pinged.contains(&enemy), "{:?} was never pinged", enemy);
+    }
+}
+
+#[test]
+fn perfect_sonar_reports_exact_positions() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.insert_resource(SonarNoise::NONE);
+    let mut events = ManualEventReader::<PingEvent>::default();
+    let mut checked = 0;
+    for _ in 0..300 {
+        app.update();
+        let pings: Vec<(Entity, Vec3)> = events.read(app.world.resource::<Events<PingEvent>>()).map(|event| (event.entity, event.position)).collect();
+        for (entity, position) in pings {
+            assert!(app.world.get::<FalseContact>(entity).is_none());
+            if let Some(transform) = app.world.get::<Transform>(entity) {
+                assert_eq!(transform.translation, position);
+                checked += 1;
+            }
+        }
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn noisy_sonar_shows_false_contacts_that_fade() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.insert_resource(SonarNoise { false_contacts: 20.0, ..SonarNoise::default() });
+    let mut seen = 0;
+    for _ in 0..300 {
+        app.update();
+        seen = seen.max(count::<FalseContact>(&mut app));
+    }
+    assert!(seen > 0);
+
+    app.insert_resource(SonarNoise::NONE);
+    run(&mut app, 180);
+    assert_eq!(count::<FalseContact>(&mut app), 0);
+}
+
+#[test]
+fn detection_falls_off_with_distance() {
+    let noise = SonarNoise::default();
+    assert_eq!(noise.detection_chance(0.0), 1.0);
+    assert!(noise.detection_chance(0.5) > noise.detection_chance(1.0));
+    assert_eq!(SonarNoise::NONE.detection_chance(1.0), 1.0);
+}
+
+#[test]
+fn biologics_echo_but_are_not_enemies() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.insert_resource(SonarNoise::NONE);
+    let biologics: Vec<Entity> = app.world.query_filtered::<Entity, With<Biologic>>().iter(&app.world).collect();
+    assert!(!biologics.is_empty());
+    for biologic in &biologics {
+        assert!(app.world.get::<Enemy>(*biologic).is_none());
+    }
+
+    let mut events = ManualEventReader::<PingEvent>::default();
+    let mut pinged = HashSet::new();
+    for _ in 0..600 {
+        app.update();
+        pinged.extend(events.read(app.world.resource::<Events<PingEvent>>()).map(|event| event.entity));
+    }
+    assert!(biologics.iter().any(|biologic| pinged.contains(biologic)));
+}
+
+#[test]
+fn contacts_are_only_seen_as_echoes_where_sonar_reports_them() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    let mut events = ManualEventReader::<PingEvent>::default();
+    let mut checked = 0;
+    for _ in 0..600 {
+        app.update();
+        let reported: Vec<Vec3> = events.read(app.world.resource::<Events<PingEvent>>())
+            .filter(|event| event.signature.is_some())
+            .map(|event| event.position)
+            .collect();
+        let echoes: Vec<Vec3> = app.world.query_filtered::<&Transform, With<Echo>>().iter(&app.world).map(|transform| transform.translation).collect();
+        for position in reported {
+            assert!(echoes.contains(&position), "no echo at {:?}", position);
+            checked += 1;
+        }
+        for sprite in app.world.query_filtered::<&Sprite, With<Signature>>().iter(&app.world) {
+            assert_eq!(sprite.color.a(), 0.0);
+        }
+    }
+    assert!(checked > 0);
+}
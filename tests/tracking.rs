@@ -0,0 +1,115 @@
+mod common;
+
+use bevy::prelude::*;
+use common::*;
+use miles_below_darkness::build_headless_app;
+use miles_below_darkness::enemy::Enemy;
+use miles_below_darkness::sonar::Pingable;
+use miles_below_darkness::sonar::SonarNoise;
+use miles_below_darkness::sonar::Signature;
+use miles_below_darkness::simulation::TICK_RATE;
+use miles_below_darkness::torpedo::EnemyTorpedo;
+use miles_below_darkness::tracking::Classification;
+use miles_below_darkness::tracking::CLASSIFY_SECONDS;
+use miles_below_darkness::tracking::TrackMarker;
+use miles_below_darkness::tracking::Tracks;
+
+#[test]
+fn detections_keep_course_and_speed() {
+    let mut tracks = Tracks::default();
+    tracks.detect(None, Vec2::new(0.0, 0.0), None, 0);
+    tracks.detect(None, Vec2::new(10.0, 0.0), None, TICK_RATE as u32);
+
+    let track = tracks.iter().next().unwrap();
+    assert_eq!(tracks.iter().count(), 1);
+    assert_eq!(track.detections, 2);
+    assert!((track.speed() - 10.0).abs() < 0.001);
+    assert!((track.course() + std::f32::consts::FRAC_PI_2).abs() < 0.001, "heading east is a quarter turn clockwise");
+    assert_eq!(track.estimated_position(2 * TICK_RATE as u32), Vec2::new(20.0, 0.0));
+}
+
+#[test]
+fn contacts_are_classified_after_time_on_track() {
+    let second = TICK_RATE as u32;
+    let classify = CLASSIFY_SECONDS as u32 * second;
+    let mut tracks = Tracks::default();
+    tracks.detect(None, Vec2::new(0.0, 0.0), Some(Signature::ENEMY_SUB), 0);
+    tracks.detect(None, Vec2::new(1.0, 0.0), Some(Signature::ENEMY_SUB), classify - second);
+    assert_eq!(tracks.iter().next().unwrap().classification, Classification::Unknown);
+    tracks.detect(None, Vec2::new(2.0, 0.0), Some(Signature::ENEMY_SUB), classify);
+    assert_eq!(tracks.iter().next().unwrap().classification, Classification::EnemySub);
+
+    // Louder contacts give themselves away sooner, clutter never does
+    let mut tracks = Tracks::default();
+    tracks.detect(None, Vec2::new(0.0, 0.0), Some(Signature::TORPEDO), 0);
+    tracks.detect(None, Vec2::new(1.0, 0.0), Some(Signature::TORPEDO), classify / 2);
+    assert_eq!(tracks.iter().next().unwrap().classification, Classification::Torpedo);
+    let mut tracks = Tracks::default();
+    tracks.detect(None, Vec2::new(0.0, 0.0), None, 0);
+    tracks.detect(None, Vec2::new(1.0, 0.0), None, 10 * classify);
+    assert_eq!(tracks.iter().next().unwrap().classification, Classification::Unknown);
+}
+
+#[test]
+fn sweeps_build_a_track_per_enemy() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.insert_resource(SonarNoise::NONE);
+    despawn_all::<EnemyTorpedo>(&mut app);
+    // A few full turns of the sweep line
+    run(&mut app, 600);
+
+    let enemies: Vec<Entity> = app.world.query_filtered::<Entity, With<Enemy>>().iter(&app.world).collect();
+    let tracks = app.world.resource::<Tracks>();
+    for enemy in &enemies {
+        let track = tracks.iter().find(|track| track.entity == Some(*enemy)).expect("enemy was never tracked");
+        assert!(track.detections >= 2);
+        assert!(track.speed() > 0.0 && track.speed() < 30.0, "speed estimate {}", track.speed());
+    }
+    let tracked = tracks.iter().count();
+    assert_eq!(count::<TrackMarker>(&mut app), tracked);
+}
+
+#[test]
+fn lost_contacts_are_remembered_then_dropped() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    app.insert_resource(SonarNoise::NONE);
+    run(&mut app, 300);
+    let tracked = app.world.resource::<Tracks>().iter().count();
+    assert!(tracked > 0);
+
+    // With nothing left to echo, no contact is detected again
+    let run_deaf = |app: &mut App, frames: u32| {
+        for _ in 0..frames {
+            let pingables: Vec<Entity> = app.world.query_filtered::<Entity, With<Pingable>>().iter(&app.world).collect();
+            for entity in pingables {
+                app.world.entity_mut(entity).remove::<Pingable>();
+            }
+            app.update();
+        }
+    };
+    run_deaf(&mut app, 60);
+    assert_eq!(app.world.resource::<Tracks>().iter().count(), tracked);
+    run_deaf(&mut app, 10 * TICK_RATE as u32);
+    assert_eq!(app.world.resource::<Tracks>().iter().count(), 0);
+}
+
+#[test]
+fn cycling_and_designating() {
+    let mut app = build_headless_app();
+    start_game(&mut app);
+    run(&mut app, 300);
+
+    tap(&mut app, KeyCode::KeyQ);
+    let selected = app.world.resource::<Tracks>().selected().map(|track| track.id);
+    assert!(selected.is_some());
+    tap(&mut app, KeyCode::KeyQ);
+    assert_ne!(app.world.resource::<Tracks>().selected().map(|track| track.id), selected);
+
+    tap(&mut app, KeyCode::KeyE);
+    let tracks = app.world.resource::<Tracks>();
+    assert_eq!(tracks.designated().map(|track| track.id), tracks.selected().map(|track| track.id));
+    tap(&mut app, KeyCode::KeyE);
+    assert!(app.world.resource::<Tracks>().designated().is_none());
+}